@@ -1,2 +1,12 @@
 #!/bin/zsh
+# The bundled data/city.list.json has one city per name and no coordinates nor populations,
+# the OpenWeather list has them all
+CITY_LIST=data/city.list.openweather.json
+if [[ ! -f $CITY_LIST ]]; then
+    curl -sSfL http://bulk.openweathermap.org/sample/city.list.json.gz | gunzip > $CITY_LIST.part \
+        && mv $CITY_LIST.part $CITY_LIST \
+        || { rm -f $CITY_LIST.part; echo "Cannot download the OpenWeather city list, using data/city.list.json"; CITY_LIST=data/city.list.json; }
+fi
+cargo run --release --bin city_index -- $CITY_LIST static/city.index
 wasm-pack build --target web --out-name weather_wasm --out-dir ./static
+wasm-pack build --target no-modules --out-name city_worker --out-dir ./static/worker
@@ -0,0 +1,83 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use yew::format::Json;
+use yew::services::storage::Area;
+use yew::services::StorageService;
+
+/// Minutes a response is served from the cache without refreshing it
+pub const DEFAULT_TTL_MINUTES: u32 = 10;
+
+/// A response body kept in local storage, parsed again by its provider when used
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CacheEntry {
+    pub provider: String,
+    pub body: String,
+    /// Unix timestamp of when the response was received
+    pub fetched_at: i64,
+}
+
+pub(crate) struct Cache {
+    storage: Option<StorageService>,
+    ttl_minutes: u32,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Cache {
+            storage: StorageService::new(Area::Local).ok(),
+            ttl_minutes: DEFAULT_TTL_MINUTES,
+        }
+    }
+
+    pub fn set_ttl(&mut self, minutes: u32) {
+        self.ttl_minutes = minutes;
+    }
+
+    fn key(kind: &str, location_key: &str) -> String {
+        format!("cache.{}.{}", kind, location_key)
+    }
+
+    pub fn get(&self, kind: &str, location_key: &str) -> Option<CacheEntry> {
+        let Json(entry) = self
+            .storage
+            .as_ref()?
+            .restore::<Json<Result<CacheEntry, Error>>>(&Self::key(kind, location_key));
+        entry.ok()
+    }
+
+    pub fn put(&mut self, kind: &str, location_key: &str, entry: &CacheEntry) {
+        if let Some(storage) = self.storage.as_mut() {
+            storage.store(&Self::key(kind, location_key), Json(entry));
+        }
+    }
+
+    pub fn remove(&mut self, kind: &str, location_key: &str) {
+        if let Some(storage) = self.storage.as_mut() {
+            storage.remove(&Self::key(kind, location_key));
+        }
+    }
+
+    pub fn is_fresh(&self, entry: &CacheEntry, now: i64) -> bool {
+        is_fresh(entry.fetched_at, now, self.ttl_minutes)
+    }
+}
+
+fn is_fresh(fetched_at: i64, now: i64, ttl_minutes: u32) -> bool {
+    // A clock turned back makes the entry stale rather than fresh for good
+    (0..i64::from(ttl_minutes) * 60).contains(&(now - fetched_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_expire_after_ttl() {
+        assert!(is_fresh(1_000, 1_000, 10));
+        assert!(is_fresh(1_000, 1_599, 10));
+        assert!(!is_fresh(1_000, 1_600, 10));
+        assert!(!is_fresh(1_000, 999, 10));
+        assert!(!is_fresh(1_000, 1_000, 0));
+    }
+}
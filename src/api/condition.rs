@@ -1,18 +1,22 @@
 macro_rules! enum_map {
     ($name: ident, $type:ident, $($attr: ident: $value: expr),*) => {
+        #[allow(clippy::enum_variant_names)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
         pub enum $name {
            $(
             $attr,
            )*
+            /// Code in this group not known yet, OpenWeather adds codes over time
+            Unknown($type),
         }
 
         impl $name {
-            pub fn from(raw_value: $type) -> Option<Self> {
+            pub fn from(raw_value: $type) -> Self {
                 match raw_value {
                     $(
-                        $value => Some($name::$attr),
+                        $value => $name::$attr,
                     )*
-                    _ => None
+                    _ => $name::Unknown(raw_value)
                 }
             }
         }
@@ -32,7 +36,7 @@ ThunderstormWithDrizzle:231,
 ThunderstormWithHeavyDrizzle:232
 );
 
-enum_map!(Drizzle, u16, 
+enum_map!(Drizzle, u16,
 LightIntensityDrizzle:300,
 Drizzle:301,
 HeavyIntensityDrizzle:302,
@@ -57,7 +61,7 @@ HeavyIntensityShowerRain:522,
 RaggedShowerRain:531
 );
 
-enum_map!(Snow, u16, 
+enum_map!(Snow, u16,
 LightSnow:600,
 Snow:601,
 HeavySnow:602,
@@ -84,7 +88,7 @@ Squalls:771,
 Tornado:781
 );
 
-enum_map!(Cloud, u16, 
+enum_map!(Cloud, u16,
 Clear:800,
 FewClouds:801,
 ScatteredClouds:802,
@@ -92,6 +96,7 @@ BrokenClouds:803,
 OvercastClouds:804
 );
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Condition {
     Thunderstorm(Thunderstorm),
     Drizzle(Drizzle),
@@ -99,18 +104,112 @@ pub enum Condition {
     Snow(Snow),
     Atmosphere(Atmosphere),
     Cloud(Cloud),
+    /// Code outside of every known group
+    Unknown(u16),
 }
 
 impl Condition {
-    pub fn from(raw_value: u16) -> Option<Self> {
-        Some(match raw_value / 100 {
-            2 => Condition::Thunderstorm(Thunderstorm::from(raw_value)?),
-            3 => Condition::Drizzle(Drizzle::from(raw_value)?),
-            5 => Condition::Rain(Rain::from(raw_value)?),
-            6 => Condition::Snow(Snow::from(raw_value)?),
-            7 => Condition::Atmosphere(Atmosphere::from(raw_value)?),
-            8 => Condition::Cloud(Cloud::from(raw_value)?),
+    pub fn from(raw_value: u16) -> Self {
+        match raw_value / 100 {
+            2 => Condition::Thunderstorm(Thunderstorm::from(raw_value)),
+            3 => Condition::Drizzle(Drizzle::from(raw_value)),
+            5 => Condition::Rain(Rain::from(raw_value)),
+            6 => Condition::Snow(Snow::from(raw_value)),
+            7 => Condition::Atmosphere(Atmosphere::from(raw_value)),
+            8 => Condition::Cloud(Cloud::from(raw_value)),
+            _ => Condition::Unknown(raw_value),
+        }
+    }
+
+    /// MetaWeather reports conditions as a short abbreviation instead of a numeric code
+    /// The full list can be found here: https://www.metaweather.com/api/#weather-states
+    pub fn code_from_abbreviation(abbreviation: &str) -> Option<u16> {
+        Some(match abbreviation {
+            "sn" => 601, // Snow
+            "sl" => 611, // Sleet
+            "h" => 613,  // Hail, closest to shower sleet
+            "t" => 211,  // Thunderstorm
+            "hr" => 502, // Heavy Rain
+            "lr" => 500, // Light Rain
+            "s" => 521,  // Showers
+            "hc" => 804, // Heavy Cloud
+            "lc" => 802, // Light Cloud
+            "c" => 800,  // Clear
             _ => return None,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_map_to_groups_at_every_boundary() {
+        let table = [
+            (0, Condition::Unknown(0)),
+            (199, Condition::Unknown(199)),
+            (
+                200,
+                Condition::Thunderstorm(Thunderstorm::ThunderstormWithLightRain),
+            ),
+            (
+                232,
+                Condition::Thunderstorm(Thunderstorm::ThunderstormWithHeavyDrizzle),
+            ),
+            (233, Condition::Thunderstorm(Thunderstorm::Unknown(233))),
+            (299, Condition::Thunderstorm(Thunderstorm::Unknown(299))),
+            (300, Condition::Drizzle(Drizzle::LightIntensityDrizzle)),
+            (321, Condition::Drizzle(Drizzle::ShowerDrizzle)),
+            (322, Condition::Drizzle(Drizzle::Unknown(322))),
+            (399, Condition::Drizzle(Drizzle::Unknown(399))),
+            (400, Condition::Unknown(400)),
+            (499, Condition::Unknown(499)),
+            (500, Condition::Rain(Rain::LightRain)),
+            (531, Condition::Rain(Rain::RaggedShowerRain)),
+            (532, Condition::Rain(Rain::Unknown(532))),
+            (599, Condition::Rain(Rain::Unknown(599))),
+            (600, Condition::Snow(Snow::LightSnow)),
+            (622, Condition::Snow(Snow::HeavyShowerSnow)),
+            (623, Condition::Snow(Snow::Unknown(623))),
+            (699, Condition::Snow(Snow::Unknown(699))),
+            (700, Condition::Atmosphere(Atmosphere::Unknown(700))),
+            (701, Condition::Atmosphere(Atmosphere::Mist)),
+            (781, Condition::Atmosphere(Atmosphere::Tornado)),
+            (782, Condition::Atmosphere(Atmosphere::Unknown(782))),
+            (799, Condition::Atmosphere(Atmosphere::Unknown(799))),
+            (800, Condition::Cloud(Cloud::Clear)),
+            (804, Condition::Cloud(Cloud::OvercastClouds)),
+            (805, Condition::Cloud(Cloud::Unknown(805))),
+            (899, Condition::Cloud(Cloud::Unknown(899))),
+            (900, Condition::Unknown(900)),
+            (u16::MAX, Condition::Unknown(u16::MAX)),
+        ];
+        for (code, condition) in table.iter() {
+            assert_eq!(Condition::from(*code), *condition, "code {}", code);
+        }
+    }
+
+    /// Whether the exact code is known, rather than only its group or nothing at all
+    fn is_known(condition: Condition) -> bool {
+        use Condition::*;
+        !matches!(
+            condition,
+            Thunderstorm(self::Thunderstorm::Unknown(_))
+                | Drizzle(self::Drizzle::Unknown(_))
+                | Rain(self::Rain::Unknown(_))
+                | Snow(self::Snow::Unknown(_))
+                | Atmosphere(self::Atmosphere::Unknown(_))
+                | Cloud(self::Cloud::Unknown(_))
+                | Unknown(_)
+        )
+    }
+
+    #[test]
+    fn every_documented_code_is_known() {
+        let known = (0..1000)
+            .filter(|code| is_known(Condition::from(*code)))
+            .count();
+        assert_eq!(known, 55);
+    }
+}
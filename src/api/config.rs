@@ -0,0 +1,32 @@
+use anyhow::Error;
+use serde::Deserialize;
+use yew::format::{Json, Nothing};
+use yew::services::fetch::{FetchTask, Request, Response};
+use yew::services::FetchService;
+use yew::Callback;
+
+/// Deployment settings, served as `config.json` next to `index.html`
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    pub api_key: Option<String>,
+    /// CORS proxy, see `Proxy::parse`
+    pub proxy: Option<String>,
+}
+
+pub struct ConfigLoader {
+    loading_task: Option<FetchTask>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        ConfigLoader { loading_task: None }
+    }
+
+    pub fn load(&mut self, callback: Callback<Response<Json<Result<Config, Error>>>>) {
+        let request = Request::get("config.json")
+            .body(Nothing)
+            .expect("Failed to create request");
+        self.loading_task = FetchService::new().fetch(request, callback).ok();
+    }
+}
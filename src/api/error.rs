@@ -0,0 +1,70 @@
+use std::fmt;
+
+/// Why the weather could not be shown
+#[derive(Clone, Debug, PartialEq)]
+pub enum WeatherError {
+    /// The request could not be sent or got no response
+    Network(String),
+    /// Unsuccessful response not covered by the other variants
+    HttpStatus(u16),
+    /// 401, the API key is missing or not valid
+    InvalidApiKey,
+    /// 429, too many requests with the API key
+    RateLimited,
+    /// 404, the provider does not know the city
+    CityNotFound(String),
+    /// The response is not in the expected shape
+    Parse(String),
+    /// No provider is configured to serve the request
+    NoProvider,
+}
+
+impl WeatherError {
+    pub fn from_status(status: u16, city: &str) -> Self {
+        use WeatherError::*;
+        match status {
+            401 => InvalidApiKey,
+            404 => CityNotFound(city.to_owned()),
+            // FetchService reports requests failed without response as 408
+            408 => Network("The request timed out or could not be sent".to_owned()),
+            429 => RateLimited,
+            _ => HttpStatus(status),
+        }
+    }
+
+    /// Whether changing the settings may fix the error
+    pub fn needs_settings(&self) -> bool {
+        matches!(self, WeatherError::InvalidApiKey | WeatherError::NoProvider)
+    }
+
+    /// What the user can do about the error
+    pub fn suggestion(&self) -> &'static str {
+        use WeatherError::*;
+        match self {
+            Network(_) => "Check your internet connection and try again.",
+            HttpStatus(_) => "The weather service is having trouble, try again later.",
+            InvalidApiKey => "Check the OpenWeather API key in the settings.",
+            RateLimited => "Too many requests were made, wait a minute and try again.",
+            CityNotFound(_) => "Search for a nearby city instead.",
+            Parse(_) => "The weather service sent unexpected data, try again later.",
+            NoProvider => "Add an OpenWeather API key in the settings.",
+        }
+    }
+}
+
+impl fmt::Display for WeatherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use WeatherError::*;
+        match self {
+            Network(reason) => write!(f, "Network error: {}", reason),
+            HttpStatus(status) => write!(f, "Weather service responded with {}", status),
+            InvalidApiKey => write!(f, "The API key is not valid"),
+            RateLimited => write!(f, "Rate limit reached"),
+            CityNotFound(city) => write!(f, "{} is not found", city),
+            Parse(reason) => write!(f, "Unexpected response: {}", reason),
+            NoProvider => write!(f, "No weather provider is available"),
+        }
+    }
+}
+
+impl std::error::Error for WeatherError {}
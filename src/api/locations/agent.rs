@@ -0,0 +1,144 @@
+use super::storage::Storage;
+use super::Location;
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use yew::agent::{Agent, AgentLink, HandlerId, Public};
+use yew::services::fetch::Response;
+use yew::utils;
+
+/// Script of the worker, built by `wasm-pack build --target no-modules` into `static/worker`
+const WORKER_SCRIPT: &str = "worker/city_worker.js";
+
+/// Loads and searches the city index in a web worker, so typing stays responsive
+pub struct LocationAgent {
+    link: AgentLink<Self>,
+    storage: Storage,
+    /// Bridges waiting on the index to load, told if it fails
+    loading: Vec<HandlerId>,
+    /// The latest search of each bridge received before the index, answered once it is loaded
+    pending: Vec<(HandlerId, Search)>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum LocationRequest {
+    /// Loads the index at the absolute url, unless it is loaded already
+    Load(String),
+    Search(Search),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Search {
+    pub query: String,
+    pub limit: usize,
+    /// Ids of the recently picked cities, most recent first
+    pub recent: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum LocationResponse {
+    /// Cities found for the query, best match first, with the count of the ones left out
+    Found {
+        query: String,
+        cities: Vec<Location>,
+        more: usize,
+    },
+    Failed(String),
+}
+
+pub enum Msg {
+    IndexLoaded(Response<Result<Vec<u8>, Error>>),
+}
+
+impl Agent for LocationAgent {
+    type Reach = Public;
+    type Message = Msg;
+    type Input = LocationRequest;
+    type Output = LocationResponse;
+
+    fn create(link: AgentLink<Self>) -> Self {
+        LocationAgent {
+            link,
+            storage: Storage::new(),
+            loading: vec![],
+            pending: vec![],
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) {
+        let Msg::IndexLoaded(response) = msg;
+        let (meta, data) = response.into_parts();
+        let loaded = match (meta.status.is_success(), data) {
+            (true, Ok(bytes)) => self
+                .storage
+                .populates(bytes)
+                .map_err(|error| error.to_string()),
+            (true, Err(error)) => Err(error.to_string()),
+            (false, _) => Err(format!("City index responded with {}", meta.status)),
+        };
+        let loading = std::mem::take(&mut self.loading);
+        match loaded {
+            Ok(()) => {
+                for (id, search) in std::mem::take(&mut self.pending) {
+                    self.search(id, search);
+                }
+            }
+            Err(error) => {
+                for id in loading {
+                    let message = format!("City index is not available: {}", error);
+                    self.link.respond(id, LocationResponse::Failed(message));
+                }
+            }
+        }
+    }
+
+    fn handle_input(&mut self, request: Self::Input, id: HandlerId) {
+        match request {
+            LocationRequest::Load(url) => {
+                if self.storage.populated {
+                    return;
+                }
+                self.loading.push(id);
+                self.storage
+                    .load(&url, self.link.callback(Msg::IndexLoaded));
+            }
+            LocationRequest::Search(search) if self.storage.populated => self.search(id, search),
+            LocationRequest::Search(search) => {
+                self.pending.retain(|(pending, _)| *pending != id);
+                self.pending.push((id, search));
+            }
+        }
+    }
+
+    fn disconnected(&mut self, id: HandlerId) {
+        self.loading.retain(|loading| *loading != id);
+        self.pending.retain(|(pending, _)| *pending != id);
+    }
+
+    /// The worker script next to the page, which may not be at the root of the origin
+    fn name_of_resource() -> &'static str {
+        static RESOURCE: OnceLock<String> = OnceLock::new();
+        RESOURCE.get_or_init(|| {
+            let path = utils::window().location().pathname().unwrap_or_default();
+            let directory =
+                path[..path.rfind('/').map_or(0, |end| end + 1)].trim_start_matches('/');
+            format!("{}{}", directory, WORKER_SCRIPT)
+        })
+    }
+}
+
+impl LocationAgent {
+    fn search(&self, id: HandlerId, search: Search) {
+        let found = self
+            .storage
+            .find(&search.query, search.limit, &search.recent);
+        self.link.respond(
+            id,
+            LocationResponse::Found {
+                query: search.query,
+                cities: found.cities,
+                more: found.more,
+            },
+        );
+    }
+}
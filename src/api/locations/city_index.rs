@@ -0,0 +1,366 @@
+//! Binary index of the city list, built by `src/bin/city_index.rs` and searched as is
+//!
+//! Layout, integers in little endian:
+//! - `MAGIC`
+//! - city count and word count, `u32` each
+//! - cities sorted by folded name: id as `u32`, byte length of the name as `u8`,
+//!   latitude and longitude in `COORDINATE_SCALE`ths of degrees as `i32` (`NO_COORDINATE` if unknown)
+//!   and population as `u32` (0 if unknown)
+//! - words not at the start of their name, sorted by the folded name from the word on:
+//!   city index as `u32` and byte offset of the word in the name as `u8`
+//! - names of the cities one after another in UTF-8
+
+use super::Coordinates;
+use crate::util::text;
+use anyhow::{anyhow, Error};
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+const MAGIC: &[u8; 8] = b"CITYIDX2";
+const HEADER_SIZE: usize = MAGIC.len() + 8;
+const CITY_SIZE: usize = 17;
+const WORD_SIZE: usize = 5;
+/// Coordinates are kept to about a meter
+const COORDINATE_SCALE: f64 = 100_000.0;
+const NO_COORDINATE: i32 = i32::MIN;
+
+/// A city of the list the index is built from
+pub struct CityRecord {
+    /// `Name,ST,CC` when the city has a state, `Name,CC` otherwise
+    pub name: String,
+    pub id: u32,
+    pub coordinates: Option<Coordinates>,
+    pub population: Option<u32>,
+}
+
+pub struct CityIndex {
+    bytes: Vec<u8>,
+    city_count: usize,
+    word_count: usize,
+    /// Start of every name in `bytes`, followed by the end of the last one
+    name_offsets: Vec<usize>,
+}
+
+impl CityIndex {
+    /// Builds the index of the cities, leaving out the ones without a word in their name like "-,RU"
+    pub fn build(cities: impl IntoIterator<Item = CityRecord>) -> Result<Vec<u8>, Error> {
+        let mut cities = cities
+            .into_iter()
+            .filter(|city| word_starts(&city.name).next().is_some())
+            .map(|city| (text::fold(&city.name), city))
+            .collect::<Vec<_>>();
+        cities.sort_by(|(a, a_city), (b, b_city)| (a, a_city.id).cmp(&(b, b_city.id)));
+        let mut words = vec![];
+        for (index, (_, city)) in cities.iter().enumerate() {
+            let name = &city.name;
+            words.extend(
+                word_starts(name)
+                    .filter(|offset| *offset != 0)
+                    .map(|offset| (text::fold(&name[offset..]), index, offset)),
+            );
+        }
+        words.sort();
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&u32::try_from(cities.len())?.to_le_bytes());
+        bytes.extend_from_slice(&u32::try_from(words.len())?.to_le_bytes());
+        for (_, city) in &cities {
+            let length = u8::try_from(city.name.len())
+                .map_err(|_| anyhow!("\"{}\" is longer than 255 bytes", city.name))?;
+            let (latitude, longitude) = city
+                .coordinates
+                .map_or((NO_COORDINATE, NO_COORDINATE), |at| {
+                    (scale(at.latitude), scale(at.longitude))
+                });
+            bytes.extend_from_slice(&city.id.to_le_bytes());
+            bytes.push(length);
+            bytes.extend_from_slice(&latitude.to_le_bytes());
+            bytes.extend_from_slice(&longitude.to_le_bytes());
+            bytes.extend_from_slice(&city.population.unwrap_or_default().to_le_bytes());
+        }
+        for (_, index, offset) in &words {
+            bytes.extend_from_slice(&u32::try_from(*index)?.to_le_bytes());
+            bytes.push(u8::try_from(*offset)?);
+        }
+        for (_, city) in &cities {
+            bytes.extend_from_slice(city.name.as_bytes());
+        }
+        Ok(bytes)
+    }
+
+    /// Reads an index built by `build`, checking it is complete and well formed
+    pub fn parse(bytes: Vec<u8>) -> Result<Self, Error> {
+        if bytes.len() < HEADER_SIZE || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(anyhow!("Not a city index"));
+        }
+        let city_count = read_u32(&bytes, MAGIC.len()) as usize;
+        let word_count = read_u32(&bytes, MAGIC.len() + 4) as usize;
+        let names_start = city_count
+            .checked_mul(CITY_SIZE)
+            .and_then(|size| size.checked_add(word_count.checked_mul(WORD_SIZE)?))
+            .and_then(|size| size.checked_add(HEADER_SIZE))
+            .filter(|names_start| *names_start <= bytes.len())
+            .ok_or_else(|| anyhow!("City index is truncated"))?;
+        let mut name_offsets = Vec::with_capacity(city_count + 1);
+        name_offsets.push(names_start);
+        for city in 0..city_count {
+            let length = bytes[HEADER_SIZE + city * CITY_SIZE + 4] as usize;
+            name_offsets.push(name_offsets[city] + length);
+        }
+        if name_offsets[city_count] != bytes.len() {
+            return Err(anyhow!("City index names do not match their lengths"));
+        }
+        let names = std::str::from_utf8(&bytes[names_start..])?;
+        if !name_offsets
+            .iter()
+            .all(|offset| names.is_char_boundary(offset - names_start))
+        {
+            return Err(anyhow!("City index names are not valid UTF-8"));
+        }
+        let index = CityIndex {
+            bytes,
+            city_count,
+            word_count,
+            name_offsets,
+        };
+        for word in 0..word_count {
+            let (city, offset) = index.word(word);
+            let valid = city < city_count && index.name(city).is_char_boundary(offset);
+            if !valid {
+                return Err(anyhow!("City index word {} is out of bounds", word));
+            }
+        }
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.city_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.city_count == 0
+    }
+
+    pub fn id(&self, city: usize) -> usize {
+        read_u32(&self.bytes, HEADER_SIZE + city * CITY_SIZE) as usize
+    }
+
+    pub fn coordinates(&self, city: usize) -> Option<Coordinates> {
+        let position = HEADER_SIZE + city * CITY_SIZE + 5;
+        let latitude = read_u32(&self.bytes, position) as i32;
+        let longitude = read_u32(&self.bytes, position + 4) as i32;
+        Some(Coordinates {
+            latitude: f64::from(latitude) / COORDINATE_SCALE,
+            longitude: f64::from(longitude) / COORDINATE_SCALE,
+        })
+        .filter(|_| latitude != NO_COORDINATE && longitude != NO_COORDINATE)
+    }
+
+    pub fn population(&self, city: usize) -> Option<u32> {
+        Some(read_u32(&self.bytes, HEADER_SIZE + city * CITY_SIZE + 13))
+            .filter(|population| *population > 0)
+    }
+
+    pub fn name(&self, city: usize) -> &str {
+        let bytes = &self.bytes[self.name_offsets[city]..self.name_offsets[city + 1]];
+        std::str::from_utf8(bytes).unwrap_or_default()
+    }
+
+    /// City index and byte offset in its name of the word
+    fn word(&self, word: usize) -> (usize, usize) {
+        let position = HEADER_SIZE + self.city_count * CITY_SIZE + word * WORD_SIZE;
+        (
+            read_u32(&self.bytes, position) as usize,
+            self.bytes[position + 4] as usize,
+        )
+    }
+
+    /// Cities with a word starting with the folded query
+    pub fn starting_with(&self, query: &str) -> BTreeSet<usize> {
+        let names = prefix_range(self.city_count, query, |city| text::fold(self.name(city)));
+        let words = prefix_range(self.word_count, query, |word| {
+            let (city, offset) = self.word(word);
+            text::fold(&self.name(city)[offset..])
+        });
+        names.chain(words.map(|word| self.word(word).0)).collect()
+    }
+
+    /// Cities with a word whose first `length` folded characters are accepted
+    ///
+    /// Names and words are scanned in their sorted order, folded into a reused buffer,
+    /// so a beginning shared by the entries in a row is only checked once
+    pub fn beginning_with(
+        &self,
+        length: usize,
+        mut accepts: impl FnMut(&str) -> bool,
+    ) -> BTreeSet<usize> {
+        let mut found = BTreeSet::new();
+        let (mut beginning, mut previous) = (String::new(), String::new());
+        let mut accepted = false;
+        // Names starting with punctuation have their first word in the word table
+        let names = (0..self.city_count)
+            .filter(|city| self.name(*city).starts_with(char::is_alphanumeric))
+            .map(|city| (city, 0));
+        let words = (0..self.word_count).map(|word| self.word(word));
+        for (city, offset) in names.chain(words) {
+            text::fold_into(&self.name(city)[offset..], length, &mut beginning);
+            if beginning != previous {
+                accepted = accepts(&beginning);
+                std::mem::swap(&mut beginning, &mut previous);
+            }
+            if accepted {
+                found.insert(city);
+            }
+        }
+        found
+    }
+}
+
+fn scale(degrees: f64) -> i32 {
+    (degrees * COORDINATE_SCALE).round() as i32
+}
+
+fn read_u32(bytes: &[u8], position: usize) -> u32 {
+    let mut le_bytes = [0; 4];
+    le_bytes.copy_from_slice(&bytes[position..position + 4]);
+    u32::from_le_bytes(le_bytes)
+}
+
+/// Positions of the sorted keys starting with the prefix, found by binary search
+fn prefix_range(count: usize, prefix: &str, key: impl Fn(usize) -> String) -> Range<usize> {
+    let start = partition_point(0..count, |position| key(position).as_str() < prefix);
+    let end = partition_point(start..count, |position| key(position).starts_with(prefix));
+    start..end
+}
+
+/// The first position in the range for which `predicate` is false, it being true before and false after
+fn partition_point(range: Range<usize>, predicate: impl Fn(usize) -> bool) -> usize {
+    let (mut low, mut high) = (range.start, range.end);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match predicate(middle) {
+            true => low = middle + 1,
+            false => high = middle,
+        }
+    }
+    low
+}
+
+/// The name without the state and country codes following it
+pub(super) fn city_part(name: &str) -> &str {
+    name.split_once(',').map_or(name, |(city, _)| city)
+}
+
+/// Byte offsets of the words of the city name, the codes after the comma are not words
+pub(super) fn word_starts(name: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut previous = None;
+    city_part(name)
+        .char_indices()
+        .filter_map(move |(index, character)| {
+            let starts =
+                character.is_alphanumeric() && !previous.is_some_and(char::is_alphanumeric);
+            previous = Some(character);
+            Some(index).filter(|_| starts)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_index_is_searched_by_any_word() {
+        let bytes = CityIndex::build(
+            vec![
+                ("Toronto,CA", 6167865),
+                ("São Paulo,BR", 3448439),
+                ("Rio de Janeiro,BR", 3451190),
+                ("Saint-Étienne,FR", 2980291),
+                ("-,RU", 6730271),
+            ]
+            .into_iter()
+            .map(|(name, id)| record(name, id)),
+        )
+        .unwrap();
+        let index = CityIndex::parse(bytes.clone()).unwrap();
+        assert_eq!(index.len(), 4);
+        let found = |query: &str| {
+            index
+                .starting_with(query)
+                .into_iter()
+                .map(|city| (index.name(city), index.id(city)))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("sao"), vec![("São Paulo,BR", 3448439)]);
+        assert_eq!(found("etienne"), vec![("Saint-Étienne,FR", 2980291)]);
+        assert_eq!(
+            found("sa"),
+            vec![("Saint-Étienne,FR", 2980291), ("São Paulo,BR", 3448439)]
+        );
+        assert_eq!(found("janeiro,br"), vec![("Rio de Janeiro,BR", 3451190)]);
+        assert!(found("br").is_empty());
+
+        assert!(CityIndex::parse(bytes[..bytes.len() - 1].to_vec()).is_err());
+        assert!(CityIndex::parse(b"{\"Toronto,CA\": 6167865}".to_vec()).is_err());
+    }
+
+    #[test]
+    fn first_words_after_punctuation_are_searched() {
+        let bytes = CityIndex::build(
+            vec![
+                ("'s-Hertogenbosch,NL", 2747351),
+                ("'Ali Sabieh,DJ", 225284),
+                ("(Pokharithok Bajar),NP", 7949204),
+            ]
+            .into_iter()
+            .map(|(name, id)| record(name, id)),
+        )
+        .unwrap();
+        let index = CityIndex::parse(bytes).unwrap();
+        let found = |query: &str| {
+            index
+                .starting_with(query)
+                .into_iter()
+                .map(|city| index.id(city))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(found("hertogenbosch"), vec![2747351]);
+        assert_eq!(found("ali"), vec![225284]);
+        assert_eq!(found("pokharithok"), vec![7949204]);
+    }
+
+    #[test]
+    fn coordinates_and_population_are_kept() {
+        let springfield = CityRecord {
+            coordinates: Some(Coordinates {
+                latitude: 39.80172,
+                longitude: -89.64371,
+            }),
+            population: Some(116_250),
+            ..record("Springfield,IL,US", 4250542)
+        };
+        let bytes = CityIndex::build(vec![springfield, record("Halifax,CA", 6324729)]).unwrap();
+        let index = CityIndex::parse(bytes).unwrap();
+        assert_eq!(index.name(1), "Springfield,IL,US");
+        assert_eq!(
+            index.coordinates(1),
+            Some(Coordinates {
+                latitude: 39.80172,
+                longitude: -89.64371
+            })
+        );
+        assert_eq!(index.population(1), Some(116_250));
+        assert_eq!(index.coordinates(0), None);
+        assert_eq!(index.population(0), None);
+    }
+
+    fn record(name: &str, id: u32) -> CityRecord {
+        CityRecord {
+            name: name.to_owned(),
+            id,
+            coordinates: None,
+            population: None,
+        }
+    }
+}
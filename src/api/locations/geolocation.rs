@@ -0,0 +1,85 @@
+use super::Coordinates;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{Position, PositionError, PositionOptions};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
+use yew::utils;
+use yew::Callback;
+
+/// Gives up on the position after this many milliseconds
+const TIMEOUT: u32 = 10_000;
+/// Positions cached by the browser up to this many milliseconds old are accepted
+const MAXIMUM_AGE: u32 = 10 * 60 * 1000;
+/// Answers `None` after this many milliseconds if the browser never called back,
+/// as happens when the permission prompt is dismissed rather than answered
+const FALLBACK: u64 = 15_000;
+
+pub struct Geolocation {
+    locating_task: Option<LocatingTask>,
+}
+
+/// Keeps the callbacks given to the browser alive until the position is reported
+struct LocatingTask {
+    _success: Closure<dyn FnMut(Position)>,
+    _failure: Closure<dyn FnMut(PositionError)>,
+    _fallback: TimeoutTask,
+}
+
+impl Geolocation {
+    pub fn new() -> Self {
+        Geolocation {
+            locating_task: None,
+        }
+    }
+
+    /// Asks the browser for the position of the user, who may be prompted for permission
+    ///
+    /// The callback is called once, with `None` if the permission is denied, the prompt is left
+    /// unanswered or the position is unavailable.
+    /// Returns `false` if the browser does not support geolocation.
+    pub fn locate(&mut self, callback: Callback<Option<Coordinates>>) -> bool {
+        let geolocation = match utils::window().navigator().geolocation() {
+            Ok(geolocation) => geolocation,
+            Err(_) => return false,
+        };
+        let answered = Rc::new(Cell::new(false));
+        let callback = Callback::from(move |coordinates| {
+            if !answered.replace(true) {
+                callback.emit(coordinates)
+            }
+        });
+        let fallback =
+            TimeoutService::new().spawn(Duration::from_millis(FALLBACK), callback.reform(|_| None));
+        let located = callback.clone();
+        let success = Closure::wrap(Box::new(move |position: Position| {
+            let coordinates = position.coords();
+            located.emit(Some(Coordinates {
+                latitude: coordinates.latitude(),
+                longitude: coordinates.longitude(),
+            }))
+        }) as Box<dyn FnMut(Position)>);
+        let failure =
+            Closure::wrap(Box::new(move |_: PositionError| callback.emit(None))
+                as Box<dyn FnMut(PositionError)>);
+        let options = PositionOptions::new();
+        options.set_timeout(TIMEOUT);
+        options.set_maximum_age(MAXIMUM_AGE);
+        let requested = geolocation
+            .get_current_position_with_error_callback_and_options(
+                success.as_ref().unchecked_ref(),
+                Some(failure.as_ref().unchecked_ref()),
+                &options,
+            )
+            .is_ok();
+        self.locating_task = Some(LocatingTask {
+            _success: success,
+            _failure: failure,
+            _fallback: fallback,
+        })
+        .filter(|_| requested);
+        requested
+    }
+}
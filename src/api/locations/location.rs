@@ -0,0 +1,175 @@
+use super::Place;
+use crate::api::models::LocationWeather;
+use serde::{Deserialize, Serialize};
+
+/// A city to show the weather of, with every identifier known to the providers
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Location {
+    pub name: String,
+    /// City id used by OpenWeather and the bundled city list
+    pub id: Option<usize>,
+    /// Where-on-earth id used by MetaWeather, resolved lazily
+    pub woeid: Option<u64>,
+    /// Position reported by the browser, for locations found by geolocation
+    pub coordinates: Option<Coordinates>,
+    /// Country, state and size of cities picked from the bundled list
+    pub place: Option<Place>,
+}
+
+/// Latitude and longitude in degrees
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Location {
+    pub fn new(name: String, id: usize) -> Self {
+        Location {
+            name,
+            id: Some(id),
+            woeid: None,
+            coordinates: None,
+            place: None,
+        }
+    }
+
+    /// A city known only by name, resolved by the providers able to look it up
+    pub fn named(name: String) -> Self {
+        Location {
+            name,
+            id: None,
+            woeid: None,
+            coordinates: None,
+            place: None,
+        }
+    }
+
+    /// The location of the user, named once its weather comes back
+    pub fn here(coordinates: Coordinates) -> Self {
+        Location {
+            name: String::from("Current location"),
+            id: None,
+            woeid: None,
+            coordinates: Some(coordinates),
+            place: None,
+        }
+    }
+
+    /// A city of the bundled list, with the place read from its `Name,ST,CC` name
+    pub fn of_city(
+        name: String,
+        id: usize,
+        coordinates: Option<Coordinates>,
+        population: Option<u32>,
+    ) -> Self {
+        Location {
+            place: Place::parse(&name, coordinates, population),
+            ..Location::new(name, id)
+        }
+    }
+
+    /// The city name without the country code suffix
+    pub fn city_name(&self) -> &str {
+        self.name.split(',').next().unwrap_or_default()
+    }
+
+    /// The city followed by its state and country names, like "Springfield, Illinois, United States"
+    pub fn label(&self) -> String {
+        match &self.place {
+            Some(place) => {
+                let mut parts = vec![self.city_name()];
+                parts.extend(place.state_name());
+                parts.push(place.country_name());
+                parts.join(", ")
+            }
+            None => self.name.clone(),
+        }
+    }
+
+    /// Labels of the locations, followed by the position of the cities sharing a label,
+    /// as the list has many cities of the same name in the same state or country
+    pub fn labels(locations: &[Location]) -> Vec<String> {
+        let labels = locations.iter().map(Location::label).collect::<Vec<_>>();
+        labels
+            .iter()
+            .zip(locations)
+            .map(|(label, location)| {
+                let position = location.place.as_ref().and_then(Place::position);
+                match position {
+                    Some(position) if labels.iter().filter(|other| *other == label).count() > 1 => {
+                        format!("{} ({})", label, position)
+                    }
+                    _ => label.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Identifies the location, and stays the same once a position is resolved into a city
+    pub fn key(&self) -> String {
+        match (self.coordinates, self.id) {
+            (Some(coordinates), _) => {
+                format!("{:.4},{:.4}", coordinates.latitude, coordinates.longitude)
+            }
+            (None, Some(id)) => id.to_string(),
+            (None, None) => self.name.clone(),
+        }
+    }
+
+    /// Takes the name and id reported along with the weather, if they were not known
+    pub fn resolve(mut self, weather: &LocationWeather) -> Self {
+        if self.id.is_none() {
+            if let Some(name) = weather.place_name() {
+                self.name = name;
+            }
+            self.id = weather.city_id();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cities_of_the_same_label_are_told_apart_by_position() {
+        let at = |latitude, longitude| {
+            Some(Coordinates {
+                latitude,
+                longitude,
+            })
+        };
+        let cities = [
+            Location::of_city(
+                "Springfield,IL,US".to_owned(),
+                4250542,
+                at(39.80172, -89.64371),
+                None,
+            ),
+            Location::of_city(
+                "Springfield,IL,US".to_owned(),
+                4250546,
+                at(41.59, -88.06),
+                None,
+            ),
+            Location::of_city(
+                "Springfield,MA,US".to_owned(),
+                4951788,
+                at(42.10148, -72.58981),
+                None,
+            ),
+            Location::of_city("Springfield,IL,US".to_owned(), 4250547, None, None),
+        ];
+        assert_eq!(
+            Location::labels(&cities),
+            vec![
+                "Springfield, Illinois, United States (39.80°N 89.64°W)",
+                "Springfield, Illinois, United States (41.59°N 88.06°W)",
+                "Springfield, Massachusetts, United States",
+                "Springfield, Illinois, United States",
+            ]
+        );
+    }
+}
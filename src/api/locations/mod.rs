@@ -1,3 +1,13 @@
+mod agent;
+mod city_index;
+mod countries;
+mod geolocation;
+mod location;
+mod place;
 mod storage;
 
-pub use storage::Storage;
+pub use agent::{LocationAgent, LocationRequest, LocationResponse, Search};
+pub use city_index::{CityIndex, CityRecord};
+pub use geolocation::Geolocation;
+pub use location::{Coordinates, Location};
+pub use place::Place;
@@ -0,0 +1,117 @@
+use super::countries;
+use super::Coordinates;
+use serde::{Deserialize, Serialize};
+
+/// Where a city of the bundled list is, to tell apart cities of the same name
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Place {
+    /// ISO 3166 code of the country, like `US`
+    pub country: String,
+    /// Code of the state, only given for cities in the United States
+    pub state: Option<String>,
+    pub coordinates: Option<Coordinates>,
+    pub population: Option<u32>,
+}
+
+impl Place {
+    /// Reads the state and country codes following the city in `Name,ST,CC` or `Name,CC`
+    pub fn parse(
+        name: &str,
+        coordinates: Option<Coordinates>,
+        population: Option<u32>,
+    ) -> Option<Self> {
+        let (_, codes) = name.split_once(',')?;
+        let (state, country) = match codes.split_once(',') {
+            Some((state, country)) => (Some(state).filter(|state| is_code(state)), country),
+            None => (None, codes),
+        };
+        if !is_code(country) {
+            return None;
+        }
+        Some(Place {
+            country: country.to_owned(),
+            state: state.map(str::to_owned),
+            coordinates,
+            population,
+        })
+    }
+
+    /// Name of the country in English, or its code if it is unknown
+    pub fn country_name(&self) -> &str {
+        countries::country_name(&self.country).unwrap_or(&self.country)
+    }
+
+    /// Name of the state, or its code if it is unknown
+    pub fn state_name(&self) -> Option<&str> {
+        let state = self.state.as_deref()?;
+        Some(countries::state_name(&self.country, state).unwrap_or(state))
+    }
+
+    /// Latitude and longitude rounded to a hundredth of a degree, like `39.80°N 89.64°W`
+    pub fn position(&self) -> Option<String> {
+        let Coordinates {
+            latitude,
+            longitude,
+        } = self.coordinates?;
+        let north = if latitude < 0.0 { 'S' } else { 'N' };
+        let east = if longitude < 0.0 { 'W' } else { 'E' };
+        Some(format!(
+            "{:.2}°{} {:.2}°{}",
+            latitude.abs(),
+            north,
+            longitude.abs(),
+            east
+        ))
+    }
+
+    /// Flag emoji of the country, made of the regional indicators of its code
+    pub fn flag(&self) -> String {
+        self.country
+            .chars()
+            .filter_map(|letter| {
+                let offset = u32::from(letter.to_ascii_uppercase()).checked_sub(u32::from('A'))?;
+                char::from_u32(0x1F1E6 + offset).filter(|_| offset < 26)
+            })
+            .collect()
+    }
+}
+
+/// State and country codes are two uppercase letters
+fn is_code(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|letter| letter.is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_read_from_the_name() {
+        let springfield = Place::parse("Springfield,IL,US", None, Some(116_250)).unwrap();
+        assert_eq!(springfield.country_name(), "United States");
+        assert_eq!(springfield.state_name(), Some("Illinois"));
+        assert_eq!(springfield.flag(), "🇺🇸");
+        assert_eq!(springfield.position(), None);
+
+        let paris = Place::parse("Paris,FR", None, None).unwrap();
+        assert_eq!(paris.state, None);
+        assert_eq!(paris.country_name(), "France");
+        assert_eq!(paris.flag(), "🇫🇷");
+
+        let sydney = Coordinates {
+            latitude: -33.86785,
+            longitude: 151.20732,
+        };
+        let sydney = Place::parse("Sydney,AU", Some(sydney), None).unwrap();
+        assert_eq!(sydney.position().as_deref(), Some("33.87°S 151.21°E"));
+
+        assert_eq!(Place::parse("FR", None, None), None);
+        assert_eq!(Place::parse("Halifax", None, None), None);
+        assert_eq!(
+            Place::parse("Quartier,XX", None, None)
+                .unwrap()
+                .country_name(),
+            "XX"
+        );
+    }
+}
@@ -1,13 +1,68 @@
-use another_radix_trie::RadixTrie;
+use super::city_index::{self, CityIndex};
+use super::Location;
+use crate::util::text;
 use anyhow::Error;
-use wasm_bindgen::__rt::std::collections::BTreeMap;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
 use yew::format::Nothing;
 use yew::services::fetch::{FetchTask, Request, Response};
-use yew::services::FetchService;
+use yew::services::{FetchService, Task};
 use yew::Callback;
 
+/// Typos tolerated when nothing starts with the query
+const MAX_TYPOS: usize = 2;
+/// Folded names of well known cities, ranked above lesser known ones of similar names
+///
+/// Matched by name rather than id: the OpenWeather list has districts and municipalities
+/// of the same name, and which one the bundled list kept differs from city to city.
+/// Among the matches the population ranks the city itself first.
+const POPULAR_CITIES: [&str; 35] = [
+    "new york city,ny,us",
+    "london,gb",
+    "paris,fr",
+    "tokyo,jp",
+    "toronto,ca",
+    "berlin,de",
+    "los angeles,ca,us",
+    "moscow,ru",
+    "sydney,au",
+    "beijing,cn",
+    "shanghai,cn",
+    "madrid,es",
+    "rome,it",
+    "mumbai,in",
+    "delhi,in",
+    "sao paulo,br",
+    "mexico city,mx",
+    "cairo,eg",
+    "istanbul,tr",
+    "seoul,kr",
+    "hong kong,hk",
+    "singapore,sg",
+    "dubai,ae",
+    "chicago,il,us",
+    "san francisco,ca,us",
+    "vancouver,ca",
+    "buenos aires,ar",
+    "jakarta,id",
+    "bangkok,th",
+    "amsterdam,nl",
+    "barcelona,es",
+    "vienna,at",
+    "lisbon,pt",
+    "rio de janeiro,br",
+    "melbourne,au",
+];
+
+/// Cities found for a query, best match first
+pub struct Found {
+    pub cities: Vec<Location>,
+    /// Matches left out by the limit
+    pub more: usize,
+}
+
 pub struct Storage {
-    trie: RadixTrie<(String, usize)>,
+    index: Option<CityIndex>,
     pub populated: bool,
     loading_task: Option<FetchTask>,
 }
@@ -15,39 +70,215 @@ pub struct Storage {
 impl Storage {
     pub fn new() -> Self {
         Storage {
-            trie: RadixTrie::new(),
+            index: None,
             populated: false,
             loading_task: None,
         }
     }
 
-    pub fn load(&mut self, callback: Callback<Response<Result<Vec<u8>, Error>>>) {
-        if self.populated {
+    /// Requests the index, unless it is loaded or on its way
+    pub fn load(&mut self, url: &str, callback: Callback<Response<Result<Vec<u8>, Error>>>) {
+        let loading = self.loading_task.as_ref().is_some_and(Task::is_active);
+        if self.populated || loading {
             return;
         }
-        let request = Request::get("city.list.json")
+        let request = Request::get(url)
             .body(Nothing)
             .expect("Failed to create request");
         self.loading_task = FetchService::new().fetch_binary(request, callback).ok();
     }
 
-    pub fn populates(&mut self, data: Vec<u8>) {
+    /// Takes the index built by `src/bin/city_index.rs`
+    pub fn populates(&mut self, data: Vec<u8>) -> Result<(), Error> {
         if self.populated {
-            return;
+            return Ok(());
         }
+        self.index = Some(CityIndex::parse(data)?);
         self.populated = true;
-        let cities = serde_json::from_slice::<BTreeMap<String, usize>>(&data)
-            .expect("Failed to deserialize");
-        cities
-            .into_iter()
-            .for_each(|(name, id)| self.trie.insert(&name.to_lowercase(), (name, id)));
+        Ok(())
     }
 
-    pub fn find(&self, name: &str) -> Vec<(String, usize)> {
-        self.trie
-            .start_with(&name.to_lowercase())
+    /// Up to `limit` cities with a word starting with the name, ignoring case and accents
+    ///
+    /// Falls back to the cities with a word starting with up to 2 typos of the name.
+    /// Cities in `recent`, most recent first, are ranked above the others of similar names.
+    pub fn find(&self, name: &str, limit: usize, recent: &[usize]) -> Found {
+        let index = match &self.index {
+            Some(index) => index,
+            None => {
+                return Found {
+                    cities: vec![],
+                    more: 0,
+                }
+            }
+        };
+        let query = text::fold(name.trim());
+        let mut matches = index.starting_with(&query);
+        if matches.is_empty() {
+            matches = find_similar(index, &query);
+        }
+        let mut ranked = matches
             .into_iter()
-            .map(|(_, val)| val.to_owned())
-            .collect::<Vec<_>>()
+            .map(|city| (rank(index, city, &query, recent), city))
+            .collect::<Vec<_>>();
+        ranked.sort_unstable();
+        Found {
+            more: ranked.len().saturating_sub(limit),
+            cities: ranked
+                .into_iter()
+                .take(limit)
+                .map(|(_, city)| {
+                    Location::of_city(
+                        index.name(city).to_owned(),
+                        index.id(city),
+                        index.coordinates(city),
+                        index.population(city),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Orders exact names first, then recently chosen cities, popular cities,
+/// names starting with the query, larger populations and shorter names
+fn rank(
+    index: &CityIndex,
+    city: usize,
+    query: &str,
+    recent: &[usize],
+) -> (bool, usize, usize, bool, Reverse<u32>, usize) {
+    let id = index.id(city);
+    let folded = text::fold(index.name(city));
+    (
+        city_index::city_part(&folded) != query && folded != query,
+        recent
+            .iter()
+            .position(|known| *known == id)
+            .unwrap_or(usize::MAX),
+        POPULAR_CITIES
+            .iter()
+            .position(|popular| *popular == folded)
+            .unwrap_or(usize::MAX),
+        !folded.starts_with(query),
+        Reverse(index.population(city).unwrap_or_default()),
+        folded.len(),
+    )
+}
+
+/// Cities with a word starting with up to 2 typos of the query
+fn find_similar(index: &CityIndex, query: &str) -> BTreeSet<usize> {
+    let length = query.chars().count();
+    // Two typos in a short query match nearly anything
+    let limit = if length < 5 { 1 } else { MAX_TYPOS };
+    let mut typos = text::Typos::new(query, limit);
+    index.beginning_with(length, |beginning| typos.distance(beginning).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::locations::CityRecord;
+
+    fn names(found: Found) -> Vec<String> {
+        found.cities.into_iter().map(|city| city.name).collect()
+    }
+
+    fn storage() -> Storage {
+        let cities = vec![
+            ("São Paulo,BR", 3448439, None),
+            ("Rio de Janeiro,BR", 3451190, None),
+            ("Toronto,CA", 6167865, None),
+            ("Paris,FR", 2988507, Some(2_138_551)),
+            // The département, named like the city in the OpenWeather list
+            ("Paris,FR", 6455259, None),
+            ("Paris,TX,US", 4717560, None),
+            ("Parisot,FR", 2988458, None),
+            ("Villeparisis,FR", 2968705, None),
+            ("Springfield,IL,US", 4250542, Some(116_250)),
+            ("Springfield,MO,US", 4409896, Some(169_176)),
+        ];
+        let mut storage = Storage::new();
+        storage
+            .populates(
+                CityIndex::build(cities.into_iter().map(|(name, id, population)| CityRecord {
+                    name: name.to_owned(),
+                    id,
+                    coordinates: None,
+                    population,
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+        storage
+    }
+
+    #[test]
+    fn any_word_matches_without_accents() {
+        let storage = storage();
+        assert_eq!(
+            names(storage.find("sao paulo", 10, &[])),
+            vec!["São Paulo,BR"]
+        );
+        assert_eq!(
+            names(storage.find("JANEI", 10, &[])),
+            vec!["Rio de Janeiro,BR"]
+        );
+        assert!(storage.find("br", 10, &[]).cities.is_empty());
+    }
+
+    #[test]
+    fn typos_are_tolerated_when_nothing_matches() {
+        let storage = storage();
+        assert_eq!(names(storage.find("torotno", 10, &[])), vec!["Toronto,CA"]);
+        assert_eq!(
+            names(storage.find("sao poalo", 10, &[])),
+            vec!["São Paulo,BR"]
+        );
+        assert!(storage.find("halifax", 10, &[]).cities.is_empty());
+    }
+
+    #[test]
+    fn matches_are_ranked_and_capped() {
+        let storage = storage();
+        let ids = |found: Found| {
+            found
+                .cities
+                .into_iter()
+                .filter_map(|city| city.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ids(storage.find("paris", 10, &[])),
+            vec![2988507, 6455259, 4717560, 2988458]
+        );
+        assert_eq!(
+            ids(storage.find("paris", 10, &[4717560])),
+            vec![4717560, 2988507, 6455259, 2988458]
+        );
+        let found = storage.find("pari", 2, &[]);
+        assert_eq!(ids(found), vec![2988507, 6455259]);
+        assert_eq!(storage.find("pari", 2, &[]).more, 2);
+        assert_eq!(
+            ids(storage.find("springfield", 10, &[])),
+            vec![4409896, 4250542]
+        );
+    }
+
+    #[test]
+    fn found_cities_carry_their_place() {
+        let found = storage().find("springfield", 10, &[]);
+        let labels = found.cities.iter().map(Location::label).collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            vec![
+                "Springfield, Missouri, United States",
+                "Springfield, Illinois, United States"
+            ]
+        );
+        assert_eq!(
+            found.cities[0].place.as_ref().unwrap().population,
+            Some(169_176)
+        );
     }
 }
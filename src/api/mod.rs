@@ -1,11 +1,21 @@
+mod cache;
 mod condition;
+pub mod config;
+mod error;
 pub mod locations;
 mod models;
+mod persisted_state;
+pub mod providers;
 mod proxy;
-mod source;
+mod retry;
 mod task_manage;
 mod weather_api;
 
+pub use cache::DEFAULT_TTL_MINUTES;
 pub use condition::*;
+pub use error::WeatherError;
 pub use models::*;
-pub use weather_api::WeatherAPI;
+pub use persisted_state::{PersistedState, Settings, Theme, DEFAULT_REFRESH_MINUTES};
+pub use proxy::Proxy;
+pub use retry::DEFAULT_TIMEOUT_SECONDS;
+pub use weather_api::{Fetched, Origin, Reply, WeatherAPI};
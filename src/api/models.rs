@@ -1,6 +1,10 @@
 use crate::api::condition::Condition;
-use chrono::NaiveDateTime;
+use crate::api::error::WeatherError;
+use crate::api::locations::Coordinates;
+use anyhow::{anyhow, Error};
+use chrono::{DateTime, NaiveDateTime};
 use serde::Deserialize;
+use std::convert::TryFrom;
 
 #[derive(Deserialize)]
 pub struct LocationWeather {
@@ -19,6 +23,17 @@ pub struct LocationWeather {
     pub times: Times,
     #[serde(rename = "timezone")]
     timezone_offset: i64,
+    /// City the weather is reported for
+    name: Option<String>,
+    #[serde(rename = "id")]
+    city_id: Option<usize>,
+    coord: Option<Coord>,
+}
+
+#[derive(Deserialize)]
+struct Coord {
+    lat: f64,
+    lon: f64,
 }
 
 #[derive(Deserialize)]
@@ -55,6 +70,7 @@ pub struct RainSnowVolume {
 pub struct Times {
     sunrise: i64,
     sunset: i64,
+    country: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -64,44 +80,20 @@ pub struct Desc {
 }
 
 impl LocationWeather {
-    pub fn id(&self) -> Condition {
-        self.descriptions
-            .first()
-            .map(|desc| desc.id)
-            .and_then(Condition::from)
-            .expect("Unexpected condition")
+    pub fn id(&self) -> Result<Condition, WeatherError> {
+        condition(&self.descriptions)
     }
 
     pub fn description(&self) -> String {
-        let mut description = String::new();
-        for (part_index, part) in self
-            .descriptions
-            .first()
-            .map(|desc| desc.description.as_str())
-            .unwrap_or_default()
-            .split_ascii_whitespace()
-            .enumerate()
-        {
-            if part_index != 0 {
-                description.push(' ');
-            }
-            for (index, char) in part.chars().enumerate() {
-                if index == 0 {
-                    description.push(char.to_ascii_uppercase());
-                } else {
-                    description.push(char)
-                }
-            }
-        }
-        description
+        capitalized_description(&self.descriptions)
     }
 
     fn sun_rise(&self) -> NaiveDateTime {
-        NaiveDateTime::from_timestamp(self.times.sunrise + self.timezone_offset, 0)
+        local_time(self.times.sunrise, self.timezone_offset)
     }
 
     fn sun_set(&self) -> NaiveDateTime {
-        NaiveDateTime::from_timestamp(self.times.sunset + self.timezone_offset, 0)
+        local_time(self.times.sunset, self.timezone_offset)
     }
 
     pub fn sun_rise_time(&self) -> String {
@@ -115,4 +107,425 @@ impl LocationWeather {
     pub fn is_night(&self) -> bool {
         self.current_time < self.times.sunrise || self.current_time > self.times.sunset
     }
+
+    /// Name of the reporting city, formatted like the bundled city list
+    pub fn place_name(&self) -> Option<String> {
+        let name = self.name.as_deref().filter(|name| !name.is_empty())?;
+        Some(match self.times.country.as_deref() {
+            Some(country) if !country.is_empty() => format!("{},{}", name, country),
+            _ => name.to_owned(),
+        })
+    }
+
+    pub fn city_id(&self) -> Option<usize> {
+        self.city_id.filter(|id| *id != 0)
+    }
+
+    /// Position of the reporting city
+    pub fn coordinates(&self) -> Option<Coordinates> {
+        self.coord.as_ref().map(|coord| Coordinates {
+            latitude: coord.lat,
+            longitude: coord.lon,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ForecastWeather {
+    #[serde(rename = "list")]
+    pub slots: Vec<ForecastSlot>,
+    pub city: ForecastCity,
+}
+
+#[derive(Deserialize)]
+pub struct ForecastCity {
+    #[serde(rename = "timezone")]
+    timezone_offset: i64,
+}
+
+#[derive(Deserialize)]
+pub struct ForecastSlot {
+    #[serde(rename = "dt")]
+    pub time: i64,
+    #[serde(rename = "weather")]
+    pub descriptions: Vec<Desc>,
+    #[serde(rename = "main")]
+    pub temperature: Temperatures,
+    pub rain: Option<RainSnowVolume>,
+    pub snow: Option<RainSnowVolume>,
+    #[serde(rename = "pop")]
+    pub precipitation_probability: Option<f32>,
+    #[serde(rename = "sys")]
+    part_of_day: PartOfDay,
+}
+
+#[derive(Deserialize)]
+struct PartOfDay {
+    pod: String,
+}
+
+impl ForecastWeather {
+    /// Slots grouped by their local calendar day, in chronological order
+    pub fn days(&self) -> Vec<(String, Vec<&ForecastSlot>)> {
+        let mut days: Vec<(String, Vec<&ForecastSlot>)> = vec![];
+        for slot in &self.slots {
+            let day = self.local_time(slot).format("%a %d").to_string();
+            match days.last_mut() {
+                Some((last_day, slots)) if *last_day == day => slots.push(slot),
+                _ => days.push((day, vec![slot])),
+            }
+        }
+        days
+    }
+
+    pub fn local_time(&self, slot: &ForecastSlot) -> NaiveDateTime {
+        local_time(slot.time, self.city.timezone_offset)
+    }
+}
+
+impl ForecastSlot {
+    pub fn id(&self) -> Result<Condition, WeatherError> {
+        condition(&self.descriptions)
+    }
+
+    pub fn description(&self) -> String {
+        capitalized_description(&self.descriptions)
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.part_of_day.pod == "n"
+    }
+
+    /// Rain and snow volume of this 3 hour slot in mm
+    pub fn precipitation(&self) -> f32 {
+        [&self.rain, &self.snow]
+            .iter()
+            .filter_map(|volume| volume.as_ref().and_then(|volume| volume.three_hour))
+            .sum()
+    }
+}
+
+/// Government weather alerts, as given by the OpenWeather One Call API
+#[derive(Deserialize)]
+pub struct WeatherAlerts {
+    /// Left out by the API when there is no alert
+    #[serde(default)]
+    pub alerts: Vec<WeatherAlert>,
+    timezone_offset: i64,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct WeatherAlert {
+    /// Agency issuing the alert
+    pub sender_name: String,
+    pub event: String,
+    pub start: i64,
+    pub end: i64,
+    pub description: String,
+}
+
+/// How dangerous an alert is, read from its event name as the API does not tell
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Severity {
+    Minor,
+    Moderate,
+    Severe,
+    Extreme,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Minor => "Minor",
+            Severity::Moderate => "Moderate",
+            Severity::Severe => "Severe",
+            Severity::Extreme => "Extreme",
+        }
+    }
+}
+
+impl WeatherAlerts {
+    /// Alerts not over yet, most severe first
+    pub fn active(&self, now: i64) -> Vec<&WeatherAlert> {
+        let mut active = self
+            .alerts
+            .iter()
+            .filter(|alert| alert.end > now)
+            .collect::<Vec<_>>();
+        active.sort_by_key(|alert| (std::cmp::Reverse(alert.severity()), alert.start));
+        active
+    }
+
+    /// When the alert is in effect, in the local time of the city
+    pub fn time_window(&self, alert: &WeatherAlert) -> String {
+        let format = |timestamp| {
+            local_time(timestamp, self.timezone_offset)
+                .format("%a %d %H:%M")
+                .to_string()
+        };
+        format!("{} – {}", format(alert.start), format(alert.end))
+    }
+}
+
+impl WeatherAlert {
+    /// National services name their alerts by level ("Tornado Warning", "Flood Watch")
+    /// or by color ("Orange Wind Warning"), both are understood
+    pub fn severity(&self) -> Severity {
+        let event = self.event.to_lowercase();
+        let has = |word: &str| event.split_whitespace().any(|part| part == word);
+        if has("extreme") || has("emergency") || has("red") {
+            Severity::Extreme
+        } else if has("warning") || has("orange") {
+            Severity::Severe
+        } else if has("watch") || has("yellow") {
+            Severity::Moderate
+        } else {
+            Severity::Minor
+        }
+    }
+
+    /// Identifies the alert across refreshes, to keep it hidden once dismissed
+    pub fn key(&self) -> String {
+        format!("{}|{}|{}", self.sender_name, self.event, self.start)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct MetaWeatherSearchResult {
+    pub woeid: u64,
+}
+
+#[derive(Deserialize)]
+pub struct MetaWeatherLocation {
+    title: String,
+    consolidated_weather: Vec<MetaWeatherDay>,
+    time: String,
+    sun_rise: String,
+    sun_set: String,
+}
+
+#[derive(Deserialize)]
+struct MetaWeatherDay {
+    weather_state_name: String,
+    weather_state_abbr: String,
+    min_temp: f32,
+    max_temp: f32,
+    the_temp: f32,
+    /// Miles per hour
+    wind_speed: f32,
+    wind_direction: f32,
+    /// Millibar, same as hPa
+    air_pressure: f32,
+    humidity: isize,
+    /// Miles
+    visibility: Option<f32>,
+}
+
+impl TryFrom<MetaWeatherLocation> for LocationWeather {
+    type Error = Error;
+
+    fn try_from(location: MetaWeatherLocation) -> Result<Self, Self::Error> {
+        const METERS_PER_MILE: f32 = 1609.344;
+        const METERS_PER_SECOND_PER_MPH: f32 = 0.44704;
+
+        let today = location
+            .consolidated_weather
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("MetaWeather returned no weather"))?;
+        let id = Condition::code_from_abbreviation(&today.weather_state_abbr).ok_or_else(|| {
+            anyhow!(
+                "Unexpected MetaWeather condition: {}",
+                today.weather_state_abbr
+            )
+        })?;
+        let current_time = DateTime::parse_from_rfc3339(&location.time)?;
+        let sunrise = DateTime::parse_from_rfc3339(&location.sun_rise)?;
+        let sunset = DateTime::parse_from_rfc3339(&location.sun_set)?;
+        Ok(LocationWeather {
+            descriptions: vec![Desc {
+                id,
+                description: today.weather_state_name,
+            }],
+            temperature: Temperatures {
+                temp: today.the_temp,
+                // MetaWeather does not provide an apparent temperature
+                feels_like: today.the_temp,
+                temp_min: today.min_temp,
+                temp_max: today.max_temp,
+                pressure: today.air_pressure.round() as isize,
+                humidity: today.humidity,
+            },
+            visibility: today
+                .visibility
+                .map(|visibility| (visibility * METERS_PER_MILE).round() as usize),
+            wind: Wind {
+                speed: today.wind_speed * METERS_PER_SECOND_PER_MPH,
+                degree: today.wind_direction,
+            },
+            clouds: None,
+            rain: None,
+            snow: None,
+            current_time: current_time.timestamp(),
+            times: Times {
+                sunrise: sunrise.timestamp(),
+                sunset: sunset.timestamp(),
+                country: None,
+            },
+            timezone_offset: current_time.offset().local_minus_utc() as i64,
+            name: Some(location.title),
+            city_id: None,
+            coord: None,
+        })
+    }
+}
+
+fn condition(descriptions: &[Desc]) -> Result<Condition, WeatherError> {
+    let code = descriptions
+        .first()
+        .map(|desc| desc.id)
+        .ok_or_else(|| WeatherError::Parse("Weather condition is missing".to_owned()))?;
+    Ok(Condition::from(code))
+}
+
+fn capitalized_description(descriptions: &[Desc]) -> String {
+    let mut description = String::new();
+    for (part_index, part) in descriptions
+        .first()
+        .map(|desc| desc.description.as_str())
+        .unwrap_or_default()
+        .split_ascii_whitespace()
+        .enumerate()
+    {
+        if part_index != 0 {
+            description.push(' ');
+        }
+        for (index, char) in part.chars().enumerate() {
+            if index == 0 {
+                description.push(char.to_ascii_uppercase());
+            } else {
+                description.push(char)
+            }
+        }
+    }
+    description
+}
+
+fn local_time(timestamp: i64, timezone_offset: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(timestamp + timezone_offset, 0)
+        .unwrap_or_default()
+        .naive_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_weather_converts_to_location_weather() {
+        let location = serde_json::from_str::<MetaWeatherLocation>(
+            r#"{
+                "title": "Toronto",
+                "consolidated_weather": [{
+                    "weather_state_name": "Light Cloud",
+                    "weather_state_abbr": "lc",
+                    "min_temp": 18.5,
+                    "max_temp": 27.1,
+                    "the_temp": 25.3,
+                    "wind_speed": 10.0,
+                    "wind_direction": 240.5,
+                    "air_pressure": 1014.5,
+                    "humidity": 60,
+                    "visibility": 10.0
+                }],
+                "time": "2020-07-13T11:03:42.587430-04:00",
+                "sun_rise": "2020-07-13T05:47:53.345565-04:00",
+                "sun_set": "2020-07-13T20:59:34.957007-04:00"
+            }"#,
+        )
+        .unwrap();
+        let weather = LocationWeather::try_from(location).unwrap();
+        assert_eq!(weather.description(), "Light Cloud");
+        assert_eq!(weather.temperature.pressure, 1015);
+        assert_eq!(weather.visibility, Some(16093));
+        assert!((weather.wind.speed - 4.4704).abs() < 1e-4);
+        assert_eq!(weather.sun_rise_time(), "05:47");
+        assert_eq!(weather.sun_set_time(), "20:59");
+        assert!(!weather.is_night());
+        assert_eq!(weather.place_name(), Some("Toronto".to_owned()));
+        assert_eq!(weather.city_id(), None);
+    }
+
+    #[test]
+    fn unexpected_conditions_are_errors_rather_than_panics() {
+        let weather = |conditions: &str| {
+            serde_json::from_str::<LocationWeather>(&format!(
+                r#"{{
+                    "weather": {},
+                    "main": {{"temp": 20.0, "feels_like": 20.0, "temp_min": 18.0, "temp_max": 22.0, "pressure": 1013, "humidity": 50}},
+                    "wind": {{"speed": 3.0, "deg": 180.0}},
+                    "dt": 1594641600,
+                    "sys": {{"sunrise": 1594632000, "sunset": 1594686000}},
+                    "timezone": 0
+                }}"#,
+                conditions
+            ))
+            .unwrap()
+        };
+        assert!(matches!(weather("[]").id(), Err(WeatherError::Parse(_))));
+        assert_eq!(
+            weather(r#"[{"id": 950, "description": "calm"}]"#).id(),
+            Ok(Condition::Unknown(950))
+        );
+    }
+
+    #[test]
+    fn alerts_are_ranked_by_severity_until_they_end() {
+        let alerts = serde_json::from_str::<WeatherAlerts>(
+            r#"{
+                "lat": 39.8017,
+                "lon": -89.6437,
+                "timezone": "America/Chicago",
+                "timezone_offset": -18000,
+                "alerts": [{
+                    "sender_name": "NWS Lincoln IL",
+                    "event": "Wind Advisory",
+                    "start": 1594641600,
+                    "end": 1594663200,
+                    "description": "Southwest winds 20 to 30 mph.",
+                    "tags": ["Wind"]
+                }, {
+                    "sender_name": "NWS Lincoln IL",
+                    "event": "Tornado Warning",
+                    "start": 1594645200,
+                    "end": 1594648800,
+                    "description": "At 8:05 AM CDT, a severe thunderstorm capable of producing a tornado was located near Springfield.",
+                    "tags": ["Tornado"]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let events = |now| {
+            alerts
+                .active(now)
+                .into_iter()
+                .map(|alert| (alert.severity(), alert.event.as_str()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            events(1594641600),
+            vec![
+                (Severity::Severe, "Tornado Warning"),
+                (Severity::Minor, "Wind Advisory")
+            ]
+        );
+        assert_eq!(events(1594648800), vec![(Severity::Minor, "Wind Advisory")]);
+        assert_eq!(
+            alerts.time_window(&alerts.alerts[1]),
+            "Mon 13 08:00 – Mon 13 09:00"
+        );
+
+        let quiet = serde_json::from_str::<WeatherAlerts>(r#"{"timezone_offset": 0}"#).unwrap();
+        assert!(quiet.active(0).is_empty());
+    }
 }
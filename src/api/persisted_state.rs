@@ -0,0 +1,271 @@
+use super::locations::Location;
+use crate::util::units::Units;
+use anyhow::Error;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use yew::format::Json;
+use yew::services::StorageService;
+
+/// Key of the current schema, a schema change gets a new key and a migration from the previous one
+const STATE_KEY: &str = "state.v1";
+/// Where a state that cannot be read at all is kept, so it can be recovered by hand
+const CORRUPTED_STATE_KEY: &str = "state.corrupted";
+/// Keys written before the state was versioned
+const LEGACY_LOCATION_KEY: &str = "location";
+const LEGACY_LOCATIONS_KEY: &str = "locations";
+const LEGACY_API_KEY_KEY: &str = "apikey";
+const LEGACY_PROXY_KEY: &str = "proxy";
+
+/// Minutes between refreshing the weather while the page is visible
+pub const DEFAULT_REFRESH_MINUTES: u32 = 15;
+
+/// Everything remembered between launches, kept in local storage as JSON
+///
+/// Malformed fields fall back to their defaults without failing the rest of the state
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PersistedState {
+    /// The saved cities, in the order they are paged through
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub locations: Vec<Location>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub settings: Settings,
+    /// Unix timestamp of the last weather received
+    #[serde(default, deserialize_with = "lenient")]
+    pub last_refresh: Option<i64>,
+    /// Ids of the cities picked from the search bar, most recent first
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub recent_cities: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    #[serde(default, deserialize_with = "lenient")]
+    pub api_key: Option<String>,
+    /// CORS proxy, see `Proxy::parse`
+    #[serde(default, deserialize_with = "lenient")]
+    pub proxy: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub units: Units,
+    /// Name of the provider to try first
+    #[serde(default, deserialize_with = "lenient")]
+    pub provider: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub theme: Theme,
+    /// Minutes weather is served from the cache before being requested again
+    #[serde(default, deserialize_with = "lenient")]
+    pub cache_minutes: Option<u32>,
+    /// Minutes between refreshing the weather automatically, 0 turns it off
+    #[serde(default, deserialize_with = "lenient")]
+    pub refresh_minutes: Option<u32>,
+    /// Seconds a request waits for its response, 0 waits indefinitely
+    #[serde(default, deserialize_with = "lenient")]
+    pub timeout_seconds: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follows the color scheme of the system
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Auto, Theme::Light, Theme::Dark];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Auto => "auto",
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|theme| theme.name() == name)
+    }
+}
+
+impl PersistedState {
+    /// Reads the state, migrating the entries of older versions on first load
+    ///
+    /// Returns a warning along with the state if the stored state was unreadable
+    pub fn restore(storage: &mut StorageService) -> (Self, Option<String>) {
+        let raw = match storage.restore::<Result<String, Error>>(STATE_KEY) {
+            Ok(raw) => raw,
+            Err(_) => {
+                let state = Self::migrate(storage);
+                state.store(storage);
+                return (state, None);
+            }
+        };
+        match Self::parse(&raw) {
+            Ok(state) => (state, None),
+            Err(error) => {
+                storage.store(CORRUPTED_STATE_KEY, Ok(raw));
+                let warning = format!(
+                    "Saved state is unreadable and was moved to \"{}\": {}",
+                    CORRUPTED_STATE_KEY, error
+                );
+                let state = Self::migrate(storage);
+                state.store(storage);
+                (state, Some(warning))
+            }
+        }
+    }
+
+    pub fn store(&self, storage: &mut StorageService) {
+        storage.store(STATE_KEY, Json(self));
+    }
+
+    fn parse(raw: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(raw)
+    }
+
+    /// Moves the entries written before the state was versioned into the state
+    fn migrate(storage: &mut StorageService) -> Self {
+        let mut restore = |key: &str| {
+            let entry = storage.restore::<Result<String, Error>>(key).ok();
+            storage.remove(key);
+            entry
+        };
+        Self::from_legacy(
+            restore(LEGACY_LOCATIONS_KEY),
+            restore(LEGACY_LOCATION_KEY),
+            restore(LEGACY_API_KEY_KEY),
+            restore(LEGACY_PROXY_KEY),
+        )
+    }
+
+    fn from_legacy(
+        locations: Option<String>,
+        location: Option<String>,
+        api_key: Option<String>,
+        proxy: Option<String>,
+    ) -> Self {
+        let mut locations = locations
+            .and_then(|locations| serde_json::from_str::<Value>(&locations).ok())
+            .map(salvage_list)
+            .unwrap_or_default();
+        if locations.is_empty() {
+            locations.extend(location.as_deref().and_then(parse_legacy_location));
+        }
+        let non_empty = |setting: Option<String>| setting.filter(|setting| !setting.is_empty());
+        PersistedState {
+            locations,
+            settings: Settings {
+                api_key: non_empty(api_key),
+                proxy: non_empty(proxy),
+                ..Settings::default()
+            },
+            last_refresh: None,
+            recent_cities: vec![],
+        }
+    }
+}
+
+/// Parses the `name|id` location, keeping the name if the id is broken
+fn parse_legacy_location(entry: &str) -> Option<Location> {
+    let mut split = entry.splitn(2, '|');
+    let name = split
+        .next()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())?;
+    Some(
+        match split.next().and_then(|id| id.trim().parse::<usize>().ok()) {
+            Some(id) => Location::new(name.to_owned(), id),
+            None => Location::named(name.to_owned()),
+        },
+    )
+}
+
+fn salvage_list<T: DeserializeOwned>(value: Value) -> Vec<T> {
+    match value {
+        Value::Array(items) => items
+            .into_iter()
+            .filter_map(|item| serde_json::from_value(item).ok())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+fn lenient<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).unwrap_or_default())
+}
+
+/// Keeps the well formed items of a list
+fn lenient_list<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    Value::deserialize(deserializer).map(salvage_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_entries_are_migrated() {
+        let state = PersistedState::from_legacy(
+            None,
+            Some("Halifax,CA|6324729".to_owned()),
+            Some("key".to_owned()),
+            Some(String::new()),
+        );
+        assert_eq!(
+            state.locations,
+            vec![Location::new("Halifax,CA".to_owned(), 6324729)]
+        );
+        assert_eq!(state.settings.api_key, Some("key".to_owned()));
+        assert_eq!(state.settings.proxy, None);
+
+        let state = PersistedState::from_legacy(None, Some("Halifax,CA|".to_owned()), None, None);
+        assert_eq!(
+            state.locations,
+            vec![Location::named("Halifax,CA".to_owned())]
+        );
+        let state = PersistedState::from_legacy(None, Some("|".to_owned()), None, None);
+        assert!(state.locations.is_empty());
+    }
+
+    #[test]
+    fn malformed_fields_fall_back_to_defaults() {
+        let state = PersistedState::parse(
+            r#"{
+                "locations": [
+                    {"name": "Toronto,CA", "id": 6167865},
+                    {"name": 42},
+                    {"name": "Halifax,CA", "id": 6324729}
+                ],
+                "settings": {"apiKey": "key", "units": "kelvin", "theme": "dark"},
+                "lastRefresh": "yesterday"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            state.locations,
+            vec![
+                Location::new("Toronto,CA".to_owned(), 6167865),
+                Location::new("Halifax,CA".to_owned(), 6324729),
+            ]
+        );
+        assert_eq!(state.settings.api_key, Some("key".to_owned()));
+        assert_eq!(state.settings.units, Units::Metric);
+        assert_eq!(state.settings.theme, Theme::Dark);
+        assert_eq!(state.last_refresh, None);
+
+        assert!(PersistedState::parse("{\"locations\": [").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::api::error::WeatherError;
+use crate::api::retry;
+use chrono::{DateTime, Duration, Utc};
+
+/// Recent failures of a provider, so a flapping provider is skipped for a while
+#[derive(Default)]
+pub struct Health {
+    consecutive_failures: u32,
+    skip_until: Option<DateTime<Utc>>,
+}
+
+impl Health {
+    const BASE_COOLDOWN_SECONDS: i64 = 60;
+    const MAX_COOLDOWN_SECONDS: i64 = 15 * 60;
+
+    pub fn is_available(&self, now: DateTime<Utc>) -> bool {
+        self.skip_until
+            .map(|skip_until| now >= skip_until)
+            .unwrap_or(true)
+    }
+
+    /// Every consecutive failure doubles the cooldown, up to 15 minutes
+    pub fn record_failure(&mut self, now: DateTime<Utc>) {
+        self.consecutive_failures += 1;
+        let cooldown = Self::BASE_COOLDOWN_SECONDS
+            .saturating_mul(1 << (self.consecutive_failures - 1).min(16))
+            .min(Self::MAX_COOLDOWN_SECONDS);
+        self.skip_until.replace(now + Duration::seconds(cooldown));
+    }
+
+    /// Only errors of the provider itself count as failures, not a city it does not know or a wrong key
+    pub fn record_error(&mut self, error: &WeatherError, now: DateTime<Utc>) {
+        if retry::is_provider_fault(error) {
+            self.record_failure(now);
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.skip_until = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cooldown_grows_with_failures_and_resets_on_success() {
+        let now = Utc::now();
+        let mut health = Health::default();
+        assert!(health.is_available(now));
+        health.record_failure(now);
+        assert!(!health.is_available(now + Duration::seconds(59)));
+        assert!(health.is_available(now + Duration::seconds(60)));
+        health.record_failure(now);
+        assert!(!health.is_available(now + Duration::seconds(119)));
+        for _ in 0..10 {
+            health.record_failure(now);
+        }
+        assert!(health.is_available(now + Duration::minutes(15)));
+        health.record_success();
+        assert!(health.is_available(now));
+    }
+
+    #[test]
+    fn only_provider_faults_are_recorded() {
+        let now = Utc::now();
+        let mut health = Health::default();
+        health.record_error(&WeatherError::from_status(404, "Toronto"), now);
+        health.record_error(&WeatherError::from_status(401, "Toronto"), now);
+        assert!(health.is_available(now));
+        assert_eq!(health.consecutive_failures, 0);
+        assert_eq!(health.skip_until, None);
+
+        health.record_error(&WeatherError::Parse("expected value".to_owned()), now);
+        assert!(!health.is_available(now));
+        health.record_success();
+        health.record_error(&WeatherError::from_status(503, "Toronto"), now);
+        assert!(!health.is_available(now));
+    }
+}
@@ -0,0 +1,89 @@
+use super::{Capabilities, WeatherProvider};
+use crate::api::locations::Location;
+use crate::api::models::{LocationWeather, MetaWeatherLocation, MetaWeatherSearchResult};
+use crate::util::url;
+use anyhow::Error;
+use std::convert::TryFrom;
+
+pub struct MetaWeather;
+
+impl WeatherProvider for MetaWeather {
+    fn name(&self) -> &'static str {
+        "MetaWeather"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            id: false,
+            name: false,
+            woeid: true,
+            coordinates: false,
+            forecast: false,
+            alerts: false,
+        }
+    }
+
+    fn support_cors(&self) -> bool {
+        false
+    }
+
+    fn weather_url(&self, location: &Location) -> Option<String> {
+        location
+            .woeid
+            .map(|woeid| format!("https://www.metaweather.com/api/location/{}", woeid))
+    }
+
+    fn parse_weather(&self, body: &str) -> Result<LocationWeather, Error> {
+        LocationWeather::try_from(serde_json::from_str::<MetaWeatherLocation>(body)?)
+    }
+
+    fn lookup_url(&self, location: &Location) -> Option<String> {
+        let query = match location.coordinates {
+            Some(coordinates) => format!(
+                "lattlong={},{}",
+                coordinates.latitude, coordinates.longitude
+            ),
+            None => format!("query={}", url::encode(location.city_name())),
+        };
+        Some(format!(
+            "https://www.metaweather.com/api/location/search/?{}",
+            query
+        ))
+    }
+
+    fn parse_lookup(&self, body: &str, mut location: Location) -> Result<Option<Location>, Error> {
+        let result = serde_json::from_str::<Vec<MetaWeatherSearchResult>>(body)?
+            .into_iter()
+            .next();
+        Ok(result.map(|result| {
+            location.woeid.replace(result.woeid);
+            location
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cities_without_woeid_are_looked_up_instead_of_panicking() {
+        let toronto = Location::new("Toronto,CA".to_owned(), 6167865);
+        assert_eq!(MetaWeather.weather_url(&toronto), None);
+        assert_eq!(
+            MetaWeather.lookup_url(&toronto).as_deref(),
+            Some("https://www.metaweather.com/api/location/search/?query=Toronto")
+        );
+        assert_eq!(
+            MetaWeather.parse_lookup("[]", toronto.clone()).unwrap(),
+            None
+        );
+        let found = MetaWeather
+            .parse_lookup(r#"[{"woeid": 4118}]"#, toronto.clone())
+            .unwrap();
+        assert_eq!(found.and_then(|location| location.woeid), Some(4118));
+        // A body of the wrong shape is a parse error, which fails over to the next provider
+        assert!(MetaWeather.parse_lookup("<html>", toronto).is_err());
+        assert!(MetaWeather.parse_weather("{}").is_err());
+    }
+}
@@ -0,0 +1,99 @@
+mod health;
+mod meta_weather;
+mod open_weather;
+
+use super::locations::{Coordinates, Location};
+use super::models::{ForecastWeather, LocationWeather, WeatherAlerts};
+use anyhow::{anyhow, Error};
+
+pub(crate) use health::Health;
+pub use meta_weather::MetaWeather;
+pub use open_weather::OpenWeather;
+
+/// What kind of locations and data a provider is able to serve
+#[derive(Clone, Copy, Default)]
+pub struct Capabilities {
+    /// Locations can be requested by OpenWeather city id
+    pub id: bool,
+    /// Locations can be requested by city name
+    pub name: bool,
+    /// Locations can be requested by where-on-earth id
+    pub woeid: bool,
+    /// Locations can be requested by latitude and longitude
+    pub coordinates: bool,
+    /// Multi-day forecast is available
+    pub forecast: bool,
+    /// Government weather alerts are available, by latitude and longitude
+    pub alerts: bool,
+}
+
+impl Capabilities {
+    /// Whether the location can be requested without looking it up first
+    pub fn locates(&self, location: &Location) -> bool {
+        self.name
+            || (self.id && location.id.is_some())
+            || (self.woeid && location.woeid.is_some())
+            || (self.coordinates && location.coordinates.is_some())
+    }
+}
+
+/// A source of weather data
+///
+/// Providers only build urls and parse response bodies,
+/// `WeatherAPI` takes care of fetching, proxying and task management.
+pub trait WeatherProvider {
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Providers not supporting CORS are requested through the proxy
+    fn support_cors(&self) -> bool;
+
+    /// Providers needing an API key are skipped until one is set
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn set_api_key(&mut self, _key: Option<String>) {}
+
+    /// Whether the provider has everything it needs to send requests
+    fn is_configured(&self) -> bool {
+        true
+    }
+
+    /// Url for the current weather, `None` if the location needs to be looked up first
+    fn weather_url(&self, location: &Location) -> Option<String>;
+
+    /// Url for the current weather at the given position
+    fn weather_url_by_coordinates(&self, _coordinates: &Coordinates) -> Option<String> {
+        None
+    }
+
+    fn parse_weather(&self, body: &str) -> Result<LocationWeather, Error>;
+
+    /// Url to resolve the location into an identifier this provider understands
+    fn lookup_url(&self, _location: &Location) -> Option<String> {
+        None
+    }
+
+    /// Fills the resolved identifier into the location, `None` if the location is not known
+    fn parse_lookup(&self, _body: &str, _location: Location) -> Result<Option<Location>, Error> {
+        Err(anyhow!("{} does not look up locations", self.name()))
+    }
+
+    fn forecast_url(&self, _location: &Location) -> Option<String> {
+        None
+    }
+
+    fn parse_forecast(&self, _body: &str) -> Result<ForecastWeather, Error> {
+        Err(anyhow!("{} does not provide forecast", self.name()))
+    }
+
+    fn alerts_url(&self, _coordinates: &Coordinates) -> Option<String> {
+        None
+    }
+
+    fn parse_alerts(&self, _body: &str) -> Result<WeatherAlerts, Error> {
+        Err(anyhow!("{} does not provide alerts", self.name()))
+    }
+}
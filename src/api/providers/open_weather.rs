@@ -0,0 +1,109 @@
+use super::{Capabilities, WeatherProvider};
+use crate::api::locations::{Coordinates, Location};
+use crate::api::models::{ForecastWeather, LocationWeather, WeatherAlerts};
+use crate::util::url;
+use anyhow::Error;
+
+#[derive(Default)]
+pub struct OpenWeather {
+    api_key: Option<String>,
+}
+
+impl OpenWeather {
+    fn api_key(&self) -> &str {
+        self.api_key.as_deref().unwrap_or_default()
+    }
+}
+
+impl WeatherProvider for OpenWeather {
+    fn name(&self) -> &'static str {
+        "OpenWeather"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            id: true,
+            name: true,
+            woeid: false,
+            coordinates: true,
+            forecast: true,
+            alerts: true,
+        }
+    }
+
+    fn support_cors(&self) -> bool {
+        true
+    }
+
+    fn requires_api_key(&self) -> bool {
+        true
+    }
+
+    fn set_api_key(&mut self, key: Option<String>) {
+        self.api_key = key;
+    }
+
+    fn is_configured(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    // Everything is fetched in metric, `Units` converts values for display
+    fn weather_url(&self, location: &Location) -> Option<String> {
+        let place = match (location.id, location.coordinates) {
+            (Some(id), _) => format!("id={}", id),
+            (None, Some(_)) => return None,
+            (None, None) => format!("q={}", url::encode(&location.name)),
+        };
+        Some(format!(
+            "https://api.openweathermap.org/data/2.5/weather?{}&appid={}&units=metric",
+            place,
+            self.api_key()
+        ))
+    }
+
+    fn weather_url_by_coordinates(&self, coordinates: &Coordinates) -> Option<String> {
+        Some(format!(
+            "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&units=metric",
+            coordinates.latitude,
+            coordinates.longitude,
+            self.api_key()
+        ))
+    }
+
+    fn parse_weather(&self, body: &str) -> Result<LocationWeather, Error> {
+        Ok(serde_json::from_str(body)?)
+    }
+
+    fn forecast_url(&self, location: &Location) -> Option<String> {
+        let place = match (location.id, location.coordinates) {
+            (Some(id), _) => format!("id={}", id),
+            (None, Some(coordinates)) => {
+                format!("lat={}&lon={}", coordinates.latitude, coordinates.longitude)
+            }
+            (None, None) => return None,
+        };
+        Some(format!(
+            "https://api.openweathermap.org/data/2.5/forecast?{}&appid={}&units=metric",
+            place,
+            self.api_key()
+        ))
+    }
+
+    fn parse_forecast(&self, body: &str) -> Result<ForecastWeather, Error> {
+        Ok(serde_json::from_str(body)?)
+    }
+
+    // Alerts come from the One Call API, which needs its own subscription on top of the key
+    fn alerts_url(&self, coordinates: &Coordinates) -> Option<String> {
+        Some(format!(
+            "https://api.openweathermap.org/data/3.0/onecall?lat={}&lon={}&exclude=current,minutely,hourly,daily&appid={}",
+            coordinates.latitude,
+            coordinates.longitude,
+            self.api_key()
+        ))
+    }
+
+    fn parse_alerts(&self, body: &str) -> Result<WeatherAlerts, Error> {
+        Ok(serde_json::from_str(body)?)
+    }
+}
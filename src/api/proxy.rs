@@ -1,7 +1,79 @@
+use crate::util::url;
+
 static CORS_PROXY: &str = "https://cors-anywhere.herokuapp.com/";
+static URL_PLACEHOLDER: &str = "{url}";
+
+/// How requests to providers without CORS support are sent
+#[derive(Clone, PartialEq, Debug)]
+pub enum Proxy {
+    /// Requests are sent directly
+    None,
+    /// The target url is appended to the proxy url, e.g. `https://cors-anywhere.herokuapp.com/`
+    Prefix(String),
+    /// The encoded target url replaces `{url}`, e.g. `http://localhost:8080/?url={url}`
+    Template(String),
+}
+
+impl Default for Proxy {
+    fn default() -> Self {
+        Proxy::Prefix(CORS_PROXY.to_owned())
+    }
+}
+
+impl Proxy {
+    /// Parses the proxy setting, `none` disables the proxy and an empty setting uses the default
+    pub fn parse(setting: &str) -> Self {
+        let setting = setting.trim();
+        if setting.is_empty() {
+            Proxy::default()
+        } else if setting.eq_ignore_ascii_case("none") {
+            Proxy::None
+        } else if setting.contains(URL_PLACEHOLDER) {
+            Proxy::Template(setting.to_owned())
+        } else {
+            Proxy::Prefix(setting.to_owned())
+        }
+    }
+
+    pub fn setting(&self) -> String {
+        match self {
+            Proxy::None => "none".to_owned(),
+            Proxy::Prefix(prefix) => prefix.to_owned(),
+            Proxy::Template(template) => template.to_owned(),
+        }
+    }
+
+    pub fn proxy<S: AsRef<str>>(&self, url: S) -> String {
+        match self {
+            Proxy::None => url.as_ref().to_owned(),
+            Proxy::Prefix(prefix) => {
+                let mut proxied = prefix.to_owned();
+                proxied.push_str(url.as_ref());
+                proxied
+            }
+            Proxy::Template(template) => {
+                template.replace(URL_PLACEHOLDER, &url::encode(url.as_ref()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-pub fn proxy<S: AsRef<str>>(url: S) -> String {
-    let mut proxied = CORS_PROXY.to_owned();
-    proxied.push_str(url.as_ref());
-    proxied
+    #[test]
+    fn proxies_by_setting() {
+        let target = "https://www.metaweather.com/api/location/4118";
+        assert_eq!(Proxy::parse("none").proxy(target), target);
+        assert_eq!(
+            Proxy::parse("").proxy(target),
+            format!("{}{}", CORS_PROXY, target)
+        );
+        assert_eq!(
+            Proxy::parse("http://localhost:8080/?url={url}").proxy(target),
+            "http://localhost:8080/?url=https%3A%2F%2Fwww.metaweather.com%2Fapi%2Flocation%2F4118"
+        );
+        assert_eq!(Proxy::parse(" NONE ").setting(), "none");
+    }
 }
@@ -0,0 +1,88 @@
+use super::error::WeatherError;
+use std::time::Duration;
+
+/// Seconds a request waits for its response before it is given up on
+pub const DEFAULT_TIMEOUT_SECONDS: u32 = 15;
+/// Times a request is sent again to the same provider before failing over
+pub(crate) const MAX_RETRIES: u32 = 3;
+
+const BASE_DELAY_MILLISECONDS: u64 = 1_000;
+/// A `Retry-After` longer than this fails over to the next provider instead of waiting
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Whether the error may go away by sending the same request again
+pub(crate) fn is_transient(error: &WeatherError) -> bool {
+    match error {
+        WeatherError::Network(_) | WeatherError::RateLimited => true,
+        WeatherError::HttpStatus(status) => (500..600).contains(status),
+        _ => false,
+    }
+}
+
+/// Whether the error says the provider is down or broken, so the next provider may do better
+pub(crate) fn is_provider_fault(error: &WeatherError) -> bool {
+    is_transient(error) || matches!(error, WeatherError::Parse(_))
+}
+
+/// Time to wait before the retry following `retries` earlier ones, `None` if it is not worth waiting
+///
+/// The delay doubles with every retry, half of it is randomized by `jitter` in `0..1`
+/// so clients failing together do not retry together.
+/// A `Retry-After` sent by the provider replaces the delay.
+pub(crate) fn retry_delay(
+    retries: u32,
+    jitter: f64,
+    retry_after: Option<Duration>,
+) -> Option<Duration> {
+    if retries >= MAX_RETRIES {
+        return None;
+    }
+    let delay = retry_after.unwrap_or_else(|| {
+        let backoff = BASE_DELAY_MILLISECONDS << retries;
+        let jittered = backoff / 2 + (backoff as f64 / 2.0 * jitter.clamp(0.0, 1.0)) as u64;
+        Duration::from_millis(jittered)
+    });
+    Some(delay).filter(|delay| *delay <= MAX_DELAY)
+}
+
+/// Reads a `Retry-After` header given in seconds, dates are not supported
+pub(crate) fn parse_retry_after(header: &str) -> Option<Duration> {
+    header.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// A number in `0..1` that differs between calls, good enough to spread retries
+pub(crate) fn jitter() -> f64 {
+    let nanoseconds = chrono::Utc::now().timestamp_subsec_nanos();
+    f64::from(nanoseconds.wrapping_mul(2_654_435_761) % 1_000_000) / 1_000_000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_double_until_retries_run_out() {
+        assert_eq!(retry_delay(0, 0.0, None), Some(Duration::from_millis(500)));
+        assert_eq!(
+            retry_delay(0, 1.0, None),
+            Some(Duration::from_millis(1_000))
+        );
+        assert_eq!(
+            retry_delay(2, 0.0, None),
+            Some(Duration::from_millis(2_000))
+        );
+        assert_eq!(retry_delay(MAX_RETRIES, 0.0, None), None);
+
+        let retry_after = parse_retry_after(" 30 ");
+        assert_eq!(
+            retry_delay(0, 0.5, retry_after),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(retry_delay(0, 0.5, parse_retry_after("120")), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+
+        assert!(is_transient(&WeatherError::HttpStatus(503)));
+        assert!(!is_transient(&WeatherError::HttpStatus(400)));
+        assert!(!is_transient(&WeatherError::InvalidApiKey));
+    }
+}
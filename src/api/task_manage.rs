@@ -1,19 +1,196 @@
+use std::collections::HashMap;
+use yew::services::interval::IntervalTask;
+use yew::services::timeout::TimeoutTask;
 use yew::services::Task;
 
+/// Keeps one task of each kind per location alive,
+/// a new task for the same location cancels the previous one
+///
+/// Every task gets a generation, so the response of a task replaced or cancelled
+/// since it was sent can be told apart and discarded
 pub(crate) struct TaskManage<T: Task> {
-    weather_fetch: Option<T>,
+    weather_fetches: HashMap<String, Tracked<T>>,
+    forecast_fetches: HashMap<String, Tracked<T>>,
+    alert_fetches: HashMap<String, Tracked<T>>,
+    last_generation: u64,
+    refresh_timer: Option<IntervalTask>,
+}
+
+pub(crate) struct Tracked<T> {
+    pub generation: u64,
+    /// Times the request was sent again after failing
+    pub retries: u32,
+    pub pending: Pending<T>,
+}
+
+/// What a location is waiting on
+pub(crate) enum Pending<T> {
+    /// A request in flight, given up on when its timeout fires
+    Fetch {
+        fetch: T,
+        _timeout: Option<TimeoutTask>,
+    },
+    /// The delay before a failed request is sent again
+    Backoff(TimeoutTask),
+}
+
+impl<T: Task> Pending<T> {
+    fn is_active(&self) -> bool {
+        match self {
+            Pending::Fetch { fetch, .. } => fetch.is_active(),
+            Pending::Backoff(timer) => timer.is_active(),
+        }
+    }
 }
 
 impl<T: Task> Default for TaskManage<T> {
     fn default() -> Self {
         TaskManage {
-            weather_fetch: None,
+            weather_fetches: HashMap::new(),
+            forecast_fetches: HashMap::new(),
+            alert_fetches: HashMap::new(),
+            last_generation: 0,
+            refresh_timer: None,
         }
     }
 }
 
 impl<T: Task> TaskManage<T> {
-    pub fn store_weather_fetch(&mut self, task: T) {
-        self.weather_fetch.replace(task);
+    /// A generation never given out before, for the next task to store
+    pub fn next_generation(&mut self) -> u64 {
+        self.last_generation += 1;
+        self.last_generation
+    }
+
+    pub fn store_weather_fetch(&mut self, key: String, tracked: Tracked<T>) {
+        self.weather_fetches.insert(key, tracked);
+    }
+
+    pub fn store_forecast_fetch(&mut self, key: String, tracked: Tracked<T>) {
+        self.forecast_fetches.insert(key, tracked);
+    }
+
+    pub fn store_alert_fetch(&mut self, key: String, tracked: Tracked<T>) {
+        self.alert_fetches.insert(key, tracked);
+    }
+
+    /// Replaces the running refresh timer, `None` stops it
+    pub fn store_refresh_timer(&mut self, timer: Option<IntervalTask>) {
+        self.refresh_timer = timer;
+    }
+
+    pub fn is_fetching_forecast(&self, key: &str) -> bool {
+        self.forecast_fetches
+            .get(key)
+            .is_some_and(|tracked| tracked.pending.is_active())
+    }
+
+    pub fn is_fetching_alerts(&self, key: &str) -> bool {
+        self.alert_fetches
+            .get(key)
+            .is_some_and(|tracked| tracked.pending.is_active())
+    }
+
+    /// Whether the weather task of the generation is the latest one of the location
+    pub fn is_current_weather(&self, key: &str, generation: u64) -> bool {
+        is_current(&self.weather_fetches, key, generation)
+    }
+
+    /// Whether the forecast task of the generation is the latest one of the location
+    pub fn is_current_forecast(&self, key: &str, generation: u64) -> bool {
+        is_current(&self.forecast_fetches, key, generation)
+    }
+
+    /// Whether the alerts task of the generation is the latest one of the location
+    pub fn is_current_alerts(&self, key: &str, generation: u64) -> bool {
+        is_current(&self.alert_fetches, key, generation)
+    }
+
+    /// Retries of the weather of the location so far, `None` if it is not being retried
+    pub fn weather_retries(&self, key: &str) -> Option<u32> {
+        self.weather_fetches
+            .get(key)
+            .map(|tracked| tracked.retries)
+            .filter(|retries| *retries > 0)
+    }
+
+    /// Drops the weather task of the location once its reply is received
+    pub fn finish_weather_fetch(&mut self, key: &str) {
+        self.weather_fetches.remove(key);
+    }
+
+    /// Drops the forecast task of the location once its reply is received
+    pub fn finish_forecast_fetch(&mut self, key: &str) {
+        self.forecast_fetches.remove(key);
+    }
+
+    /// Drops the alerts task of the location once its reply is received
+    pub fn finish_alert_fetch(&mut self, key: &str) {
+        self.alert_fetches.remove(key);
+    }
+
+    /// Cancels every task of the location, their responses are no longer current
+    pub fn cancel(&mut self, key: &str) {
+        // Dropping a task aborts it
+        self.weather_fetches.remove(key);
+        self.forecast_fetches.remove(key);
+        self.alert_fetches.remove(key);
+    }
+}
+
+fn is_current<T>(fetches: &HashMap<String, Tracked<T>>, key: &str, generation: u64) -> bool {
+    fetches
+        .get(key)
+        .is_some_and(|tracked| tracked.generation == generation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fake;
+
+    impl Task for Fake {
+        fn is_active(&self) -> bool {
+            true
+        }
+    }
+
+    impl Drop for Fake {
+        fn drop(&mut self) {}
+    }
+
+    fn tracked(generation: u64, retries: u32) -> Tracked<Fake> {
+        Tracked {
+            generation,
+            retries,
+            pending: Pending::Fetch {
+                fetch: Fake,
+                _timeout: None,
+            },
+        }
+    }
+
+    #[test]
+    fn replaced_and_cancelled_tasks_are_not_current() {
+        let mut task_manage = TaskManage::default();
+        let first = task_manage.next_generation();
+        task_manage.store_weather_fetch("Toronto".to_owned(), tracked(first, 0));
+        let second = task_manage.next_generation();
+        task_manage.store_weather_fetch("Toronto".to_owned(), tracked(second, 1));
+        let other = task_manage.next_generation();
+        task_manage.store_weather_fetch("Halifax".to_owned(), tracked(other, 0));
+        assert!(!task_manage.is_current_weather("Toronto", first));
+        assert!(task_manage.is_current_weather("Toronto", second));
+        assert!(task_manage.is_current_weather("Halifax", other));
+        assert!(!task_manage.is_current_forecast("Toronto", second));
+        assert_eq!(task_manage.weather_retries("Toronto"), Some(1));
+        assert_eq!(task_manage.weather_retries("Halifax"), None);
+
+        task_manage.cancel("Toronto");
+        assert!(!task_manage.is_current_weather("Toronto", second));
+        assert!(task_manage.is_current_weather("Halifax", other));
+        task_manage.finish_weather_fetch("Halifax");
+        assert!(!task_manage.is_current_weather("Halifax", other));
     }
 }
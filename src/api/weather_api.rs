@@ -1,26 +1,125 @@
-use super::models::LocationWeather;
-use super::proxy::proxy;
-use super::source::Source;
-use super::task_manage::TaskManage;
-use anyhow::Error;
-use yew::format::{Json, Nothing};
+use super::cache::{Cache, CacheEntry};
+use super::error::WeatherError;
+use super::locations::{Coordinates, Location};
+use super::models::{ForecastWeather, LocationWeather, WeatherAlerts};
+use super::providers::{Health, MetaWeather, OpenWeather, WeatherProvider};
+use super::proxy::Proxy;
+use super::retry::{self, DEFAULT_TIMEOUT_SECONDS};
+use super::task_manage::{Pending, TaskManage, Tracked};
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use yew::format::{Nothing, Text};
 use yew::services::fetch::{FetchTask, Request, Response};
-use yew::services::FetchService;
+use yew::services::{FetchService, IntervalService, TimeoutService};
 use yew::Callback;
 
 pub struct WeatherAPI {
-    source: Source,
+    providers: Vec<RegisteredProvider>,
+    /// Index of the provider tried before the others
+    preferred: Option<usize>,
+    proxy: Proxy,
     service: FetchService,
     task_manage: TaskManage<FetchTask>,
+    cache: Cache,
+    /// How long a request waits for its response, `None` waits indefinitely
+    timeout: Option<Duration>,
+}
+
+struct RegisteredProvider {
+    provider: Box<dyn WeatherProvider>,
+    health: Health,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RequestKind {
+    Weather,
+    Lookup,
+    Forecast,
+    Alerts,
+}
+
+impl RequestKind {
+    /// Name of the responses of this kind in the cache
+    fn cache_name(self) -> &'static str {
+        match self {
+            RequestKind::Weather | RequestKind::Lookup => "weather",
+            RequestKind::Forecast => "forecast",
+            RequestKind::Alerts => "alerts",
+        }
+    }
+}
+
+/// A request, along with what is needed to send it again or fail over from it
+#[derive(Clone)]
+struct Attempt {
+    location: Location,
+    kind: RequestKind,
+    provider: usize,
+    /// Times the request was sent again to the same provider after failing
+    retries: u32,
+    /// Error of the provider failed over from, reported if every provider fails
+    first_error: Option<WeatherError>,
+    /// Where alerts are requested for, alerts are not requested by city
+    coordinates: Option<Coordinates>,
+}
+
+/// Outcome of a request sent by `WeatherAPI`, to be handed back to `WeatherAPI::receive`
+pub struct Reply {
+    attempt: Attempt,
+    /// Generation of the task the reply is for, see `TaskManage`
+    generation: u64,
+    outcome: Outcome,
+}
+
+enum Outcome {
+    Response(Response<Text>),
+    /// No response came before the timeout
+    TimedOut,
+    /// The delay after a failure is over, the request is to be sent again
+    RetryDue,
+}
+
+impl Reply {
+    /// The location the request was sent for
+    pub fn location(&self) -> &Location {
+        &self.attempt.location
+    }
+}
+
+enum Parsed {
+    Fetched(Fetched),
+    Located(Location),
+}
+
+/// Weather data parsed and normalized from a `Reply` or the cache
+pub enum Fetched {
+    Weather(Location, Box<LocationWeather>, Origin),
+    Forecast(Location, ForecastWeather),
+    Alerts(Location, WeatherAlerts),
+}
+
+/// Where the weather comes from
+#[derive(Clone, Copy)]
+pub struct Origin {
+    /// Name of the provider serving it
+    pub provider: &'static str,
+    /// Unix timestamp of when it was received
+    pub updated: i64,
+}
+
+/// Weather data read from the cache
+pub struct Cached {
+    pub fetched: Fetched,
+    /// Fresh data does not need to be requested again
+    pub fresh: bool,
 }
 
 impl Default for WeatherAPI {
     fn default() -> Self {
-        WeatherAPI {
-            source: Source::OpenWeather,
-            service: FetchService::new(),
-            task_manage: TaskManage::default(),
-        }
+        Self::with_providers(vec![
+            Box::new(OpenWeather::default()),
+            Box::new(MetaWeather),
+        ])
     }
 }
 
@@ -29,18 +128,679 @@ impl WeatherAPI {
         Self::default()
     }
 
-    pub fn fetch(
+    /// Providers are tried in the given order, falling over to the next one on failure
+    pub fn with_providers(providers: Vec<Box<dyn WeatherProvider>>) -> Self {
+        WeatherAPI {
+            providers: providers
+                .into_iter()
+                .map(|provider| RegisteredProvider {
+                    provider,
+                    health: Health::default(),
+                })
+                .collect(),
+            preferred: None,
+            proxy: Proxy::default(),
+            service: FetchService::new(),
+            task_manage: TaskManage::default(),
+            cache: Cache::new(),
+            timeout: Some(Duration::from_secs(u64::from(DEFAULT_TIMEOUT_SECONDS))),
+        }
+    }
+
+    /// Sets the key of every provider requiring one
+    pub fn set_api_key(&mut self, key: Option<String>) {
+        self.providers
+            .iter_mut()
+            .filter(|registered| registered.provider.requires_api_key())
+            .for_each(|registered| registered.provider.set_api_key(key.clone()));
+    }
+
+    /// Sets how providers without CORS support are requested
+    pub fn set_proxy(&mut self, proxy: Proxy) {
+        self.proxy = proxy;
+    }
+
+    /// Sets how many minutes responses are served from the cache
+    pub fn set_cache_ttl(&mut self, minutes: u32) {
+        self.cache.set_ttl(minutes);
+    }
+
+    /// Sets how long a request waits for its response before it is retried, `None` waits indefinitely
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Calls back every `interval` to refresh the weather, `None` stops refreshing
+    pub fn schedule_refresh(&mut self, interval: Option<Duration>, callback: Callback<()>) {
+        let timer = interval.map(|interval| IntervalService::new().spawn(interval, callback));
+        self.task_manage.store_refresh_timer(timer);
+    }
+
+    /// Names of the providers, in the order they were given
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers
+            .iter()
+            .map(|registered| registered.provider.name())
+            .collect()
+    }
+
+    /// Tries the named provider first, the others keep their order
+    pub fn set_preferred_provider(&mut self, name: Option<&str>) {
+        self.preferred = name.and_then(|name| {
+            self.providers
+                .iter()
+                .position(|registered| registered.provider.name() == name)
+        });
+    }
+
+    fn provider(&self, index: usize) -> &dyn WeatherProvider {
+        self.providers[index].provider.as_ref()
+    }
+
+    /// Indices of the providers in the order they are tried
+    fn provider_order(&self) -> Vec<usize> {
+        let mut order = (0..self.providers.len()).collect::<Vec<_>>();
+        if let Some(preferred) = self.preferred {
+            order.retain(|index| *index != preferred);
+            order.insert(0, preferred);
+        }
+        order
+    }
+
+    /// The first provider tried after `previous` that is healthy and able to serve the request
+    ///
+    /// When every provider is cooling down, the first capable one is used anyway
+    fn next_provider(
+        &self,
+        previous: Option<usize>,
+        kind: RequestKind,
+        retry: bool,
+    ) -> Option<usize> {
+        pick_provider(
+            &self.providers,
+            &self.provider_order(),
+            previous,
+            kind,
+            retry,
+            Utc::now(),
+        )
+    }
+
+    fn track(&mut self, kind: RequestKind, key: String, tracked: Tracked<FetchTask>) {
+        match kind {
+            RequestKind::Weather | RequestKind::Lookup => {
+                self.task_manage.store_weather_fetch(key, tracked)
+            }
+            RequestKind::Forecast => self.task_manage.store_forecast_fetch(key, tracked),
+            RequestKind::Alerts => self.task_manage.store_alert_fetch(key, tracked),
+        }
+    }
+
+    fn send(
         &mut self,
-        location: usize,
-        callback: Callback<Response<Json<Result<LocationWeather, Error>>>>,
-    ) -> Result<(), Error> {
-        let fetch_url = match self.source.support_cors() {
-            true => self.source.weather_url_by_id(location),
-            false => proxy(self.source.weather_url_by_id(location)),
+        url: String,
+        attempt: Attempt,
+        callback: Callback<Reply>,
+    ) -> Result<(), WeatherError> {
+        let fetch_url = match self.provider(attempt.provider).support_cors() {
+            true => url,
+            false => self.proxy.proxy(url),
         };
-        let request = Request::get(fetch_url).body(Nothing)?;
-        let task = self.service.fetch(request, callback)?;
-        self.task_manage.store_weather_fetch(task);
+        let request = Request::get(fetch_url)
+            .body(Nothing)
+            .map_err(|error| WeatherError::Network(error.to_string()))?;
+        let generation = self.task_manage.next_generation();
+        let reply = move |attempt: Attempt| {
+            move |outcome| Reply {
+                attempt: attempt.clone(),
+                generation,
+                outcome,
+            }
+        };
+        let fetch = self
+            .service
+            .fetch(
+                request,
+                callback
+                    .reform(reply(attempt.clone()))
+                    .reform(Outcome::Response),
+            )
+            .map_err(|error| WeatherError::Network(error.to_string()))?;
+        let timeout = self.timeout.map(|timeout| {
+            let timed_out = callback
+                .reform(reply(attempt.clone()))
+                .reform(|_| Outcome::TimedOut);
+            TimeoutService::new().spawn(timeout, timed_out)
+        });
+        self.track(
+            attempt.kind,
+            attempt.location.key(),
+            Tracked {
+                generation,
+                retries: attempt.retries,
+                pending: Pending::Fetch {
+                    fetch,
+                    _timeout: timeout,
+                },
+            },
+        );
         Ok(())
     }
+
+    /// Sends the request again once `delay` is over
+    fn schedule_retry(&mut self, attempt: Attempt, delay: Duration, callback: Callback<Reply>) {
+        let generation = self.task_manage.next_generation();
+        let (kind, key, retries) = (attempt.kind, attempt.location.key(), attempt.retries);
+        let retry_due = callback.reform(move |_| Reply {
+            attempt: attempt.clone(),
+            generation,
+            outcome: Outcome::RetryDue,
+        });
+        let timer = TimeoutService::new().spawn(delay, retry_due);
+        self.track(
+            kind,
+            key,
+            Tracked {
+                generation,
+                retries,
+                pending: Pending::Backoff(timer),
+            },
+        );
+    }
+
+    fn request_weather(
+        &mut self,
+        mut attempt: Attempt,
+        callback: Callback<Reply>,
+    ) -> Result<(), WeatherError> {
+        let weather_provider = self.provider(attempt.provider);
+        let location = &attempt.location;
+        let (kind, url) = match weather_provider.capabilities().locates(location) {
+            true => weather_provider
+                .weather_url(location)
+                .or_else(|| {
+                    location.coordinates.and_then(|coordinates| {
+                        weather_provider.weather_url_by_coordinates(&coordinates)
+                    })
+                })
+                .map(|url| (RequestKind::Weather, url)),
+            false => weather_provider
+                .lookup_url(location)
+                .map(|url| (RequestKind::Lookup, url)),
+        }
+        .ok_or_else(|| WeatherError::CityNotFound(location.name.clone()))?;
+        attempt.kind = kind;
+        self.send(url, attempt, callback)
+    }
+
+    fn request_forecast(
+        &mut self,
+        attempt: Attempt,
+        callback: Callback<Reply>,
+    ) -> Result<(), WeatherError> {
+        let url = self
+            .provider(attempt.provider)
+            .forecast_url(&attempt.location)
+            .ok_or(WeatherError::NoProvider)?;
+        self.send(url, attempt, callback)
+    }
+
+    fn request_alerts(
+        &mut self,
+        attempt: Attempt,
+        callback: Callback<Reply>,
+    ) -> Result<(), WeatherError> {
+        let url = attempt
+            .coordinates
+            .and_then(|coordinates| self.provider(attempt.provider).alerts_url(&coordinates))
+            .ok_or(WeatherError::NoProvider)?;
+        self.send(url, attempt, callback)
+    }
+
+    fn request(&mut self, attempt: Attempt, callback: Callback<Reply>) -> Result<(), WeatherError> {
+        match attempt.kind {
+            RequestKind::Weather | RequestKind::Lookup => self.request_weather(attempt, callback),
+            RequestKind::Forecast => self.request_forecast(attempt, callback),
+            RequestKind::Alerts => self.request_alerts(attempt, callback),
+        }
+    }
+
+    pub fn fetch(
+        &mut self,
+        location: Location,
+        callback: Callback<Reply>,
+    ) -> Result<(), WeatherError> {
+        let provider = self
+            .next_provider(None, RequestKind::Weather, false)
+            .ok_or(WeatherError::NoProvider)?;
+        self.request_weather(
+            Attempt {
+                location,
+                kind: RequestKind::Weather,
+                provider,
+                retries: 0,
+                first_error: None,
+                coordinates: None,
+            },
+            callback,
+        )
+    }
+
+    /// Requests the forecast, unless it is already on its way
+    pub fn fetch_forecast(
+        &mut self,
+        location: Location,
+        callback: Callback<Reply>,
+    ) -> Result<(), WeatherError> {
+        if self.task_manage.is_fetching_forecast(&location.key()) {
+            return Ok(());
+        }
+        let provider = self
+            .next_provider(None, RequestKind::Forecast, false)
+            .ok_or(WeatherError::NoProvider)?;
+        self.request_forecast(
+            Attempt {
+                location,
+                kind: RequestKind::Forecast,
+                provider,
+                retries: 0,
+                first_error: None,
+                coordinates: None,
+            },
+            callback,
+        )
+    }
+
+    /// Requests the alerts in effect at the position of the location, unless they are already on their way
+    pub fn fetch_alerts(
+        &mut self,
+        location: Location,
+        coordinates: Coordinates,
+        callback: Callback<Reply>,
+    ) -> Result<(), WeatherError> {
+        if self.task_manage.is_fetching_alerts(&location.key()) {
+            return Ok(());
+        }
+        let provider = self
+            .next_provider(None, RequestKind::Alerts, false)
+            .ok_or(WeatherError::NoProvider)?;
+        self.request_alerts(
+            Attempt {
+                location,
+                kind: RequestKind::Alerts,
+                provider,
+                retries: 0,
+                first_error: None,
+                coordinates: Some(coordinates),
+            },
+            callback,
+        )
+    }
+
+    fn parse(
+        &self,
+        kind: RequestKind,
+        provider: usize,
+        body: &str,
+        location: Location,
+        updated: i64,
+    ) -> Result<Parsed, WeatherError> {
+        let weather_provider = self.provider(provider);
+        let parse_error = |error: anyhow::Error| WeatherError::Parse(error.to_string());
+        Ok(match kind {
+            RequestKind::Weather => {
+                let weather = weather_provider.parse_weather(body).map_err(parse_error)?;
+                weather.id()?;
+                Parsed::Fetched(Fetched::Weather(
+                    location.resolve(&weather),
+                    Box::new(weather),
+                    Origin {
+                        provider: weather_provider.name(),
+                        updated,
+                    },
+                ))
+            }
+            RequestKind::Lookup => Parsed::Located(
+                weather_provider
+                    .parse_lookup(body, location.clone())
+                    .map_err(parse_error)?
+                    .ok_or(WeatherError::CityNotFound(location.name))?,
+            ),
+            RequestKind::Forecast => Parsed::Fetched(Fetched::Forecast(
+                location,
+                weather_provider.parse_forecast(body).map_err(parse_error)?,
+            )),
+            RequestKind::Alerts => Parsed::Fetched(Fetched::Alerts(
+                location,
+                weather_provider.parse_alerts(body).map_err(parse_error)?,
+            )),
+        })
+    }
+
+    /// Parses the reply into weather data
+    ///
+    /// Returns `Ok(None)` when a follow-up request has been sent or scheduled instead,
+    /// either to retry after a transient failure, to fail over to the next provider
+    /// or after looking up the location. Its reply will come through the `callback`.
+    pub fn receive(
+        &mut self,
+        reply: Reply,
+        callback: Callback<Reply>,
+    ) -> Result<Option<Fetched>, WeatherError> {
+        let Reply {
+            attempt, outcome, ..
+        } = reply;
+        match attempt.kind {
+            RequestKind::Weather | RequestKind::Lookup => self
+                .task_manage
+                .finish_weather_fetch(&attempt.location.key()),
+            RequestKind::Forecast => self
+                .task_manage
+                .finish_forecast_fetch(&attempt.location.key()),
+            RequestKind::Alerts => self.task_manage.finish_alert_fetch(&attempt.location.key()),
+        }
+        let response = match outcome {
+            Outcome::Response(response) => response,
+            Outcome::TimedOut => {
+                let error = WeatherError::Network("The weather service did not respond".to_owned());
+                return self.fail(attempt, error, None, callback);
+            }
+            Outcome::RetryDue => return self.request(attempt, callback).map(|_| None),
+        };
+        let (meta, body) = response.into_parts();
+        let now = Utc::now().timestamp();
+        let parsed = match meta.status.is_success() {
+            true => body
+                .map_err(|error| WeatherError::Network(error.to_string()))
+                .and_then(|body| {
+                    let parsed = self.parse(
+                        attempt.kind,
+                        attempt.provider,
+                        &body,
+                        attempt.location.clone(),
+                        now,
+                    )?;
+                    Ok((parsed, body))
+                }),
+            false => Err(WeatherError::from_status(
+                meta.status.as_u16(),
+                &attempt.location.name,
+            )),
+        };
+        match parsed {
+            Ok((Parsed::Fetched(fetched), body)) => {
+                self.providers[attempt.provider].health.record_success();
+                let location_key = match &fetched {
+                    Fetched::Weather(location, _, _)
+                    | Fetched::Forecast(location, _)
+                    | Fetched::Alerts(location, _) => location.key(),
+                };
+                let entry = CacheEntry {
+                    provider: self.provider(attempt.provider).name().to_owned(),
+                    body,
+                    fetched_at: now,
+                };
+                self.cache
+                    .put(attempt.kind.cache_name(), &location_key, &entry);
+                Ok(Some(fetched))
+            }
+            Ok((Parsed::Located(location), _)) => self
+                .request_weather(
+                    Attempt {
+                        location,
+                        kind: RequestKind::Weather,
+                        retries: 0,
+                        ..attempt
+                    },
+                    callback,
+                )
+                .map(|_| None),
+            Err(error) => {
+                let retry_after = meta
+                    .headers
+                    .get("retry-after")
+                    .and_then(|header| header.to_str().ok())
+                    .and_then(retry::parse_retry_after);
+                self.fail(attempt, error, retry_after, callback)
+            }
+        }
+    }
+
+    /// Retries a transient failure after a delay, or fails over to the next provider
+    fn fail(
+        &mut self,
+        attempt: Attempt,
+        error: WeatherError,
+        retry_after: Option<Duration>,
+        callback: Callback<Reply>,
+    ) -> Result<Option<Fetched>, WeatherError> {
+        let delay = Some(&error)
+            .filter(|error| retry::is_transient(error))
+            .and_then(|_| retry::retry_delay(attempt.retries, retry::jitter(), retry_after));
+        if let Some(delay) = delay {
+            let retried = Attempt {
+                retries: attempt.retries + 1,
+                ..attempt
+            };
+            self.schedule_retry(retried, delay, callback);
+            return Ok(None);
+        }
+        let order = self.provider_order();
+        let next = fail_over(&mut self.providers, &order, &attempt, &error, Utc::now());
+        let first_error = attempt.first_error.clone().unwrap_or(error);
+        match next {
+            Some(next) => self
+                .request(
+                    Attempt {
+                        provider: next,
+                        retries: 0,
+                        first_error: Some(first_error),
+                        ..attempt
+                    },
+                    callback,
+                )
+                .map(|_| None),
+            None => Err(first_error),
+        }
+    }
+
+    /// Times the weather of the location was requested again after failing,
+    /// `None` if it is not being retried
+    pub fn retries(&self, location: &Location) -> Option<u32> {
+        self.task_manage.weather_retries(&location.key())
+    }
+
+    /// The weather received last for the location, if any
+    pub fn cached_weather(&self, location: &Location) -> Option<Cached> {
+        self.cached(RequestKind::Weather, location)
+    }
+
+    /// The forecast received last for the location, if any
+    pub fn cached_forecast(&self, location: &Location) -> Option<Cached> {
+        self.cached(RequestKind::Forecast, location)
+    }
+
+    /// The alerts received last for the location, if any
+    pub fn cached_alerts(&self, location: &Location) -> Option<Cached> {
+        self.cached(RequestKind::Alerts, location)
+    }
+
+    fn cached(&self, kind: RequestKind, location: &Location) -> Option<Cached> {
+        let entry = self.cache.get(kind.cache_name(), &location.key())?;
+        let provider = self
+            .providers
+            .iter()
+            .position(|registered| registered.provider.name() == entry.provider)?;
+        match self.parse(
+            kind,
+            provider,
+            &entry.body,
+            location.clone(),
+            entry.fetched_at,
+        ) {
+            Ok(Parsed::Fetched(fetched)) => Some(Cached {
+                fetched,
+                fresh: self.cache.is_fresh(&entry, Utc::now().timestamp()),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the reply answers the latest request sent for its location
+    ///
+    /// Replies to requests replaced by a newer one, or cancelled, are stale and should be discarded
+    pub fn is_current(&self, reply: &Reply) -> bool {
+        let key = reply.location().key();
+        match reply.attempt.kind {
+            RequestKind::Weather | RequestKind::Lookup => {
+                self.task_manage.is_current_weather(&key, reply.generation)
+            }
+            RequestKind::Forecast => self.task_manage.is_current_forecast(&key, reply.generation),
+            RequestKind::Alerts => self.task_manage.is_current_alerts(&key, reply.generation),
+        }
+    }
+
+    /// Cancels the requests sent for the location, their replies will be stale
+    pub fn cancel(&mut self, location: &Location) {
+        self.task_manage.cancel(&location.key());
+    }
+
+    /// Cancels the requests and drops the cached responses of a location no longer shown
+    pub fn forget(&mut self, location: &Location) {
+        self.cancel(location);
+        for kind in &[
+            RequestKind::Weather,
+            RequestKind::Forecast,
+            RequestKind::Alerts,
+        ] {
+            self.cache.remove(kind.cache_name(), &location.key());
+        }
+    }
+}
+
+/// The first provider in `order` after `previous` that is healthy and able to serve the request
+///
+/// Unless the request is retried, the first capable provider is used when every one is cooling down
+fn pick_provider(
+    providers: &[RegisteredProvider],
+    order: &[usize],
+    previous: Option<usize>,
+    kind: RequestKind,
+    retry: bool,
+    now: DateTime<Utc>,
+) -> Option<usize> {
+    let start = previous
+        .and_then(|previous| order.iter().position(|index| *index == previous))
+        .map_or(0, |position| position + 1);
+    let capable = order[start..].iter().copied().filter(|index| {
+        let provider = providers[*index].provider.as_ref();
+        let capabilities = provider.capabilities();
+        provider.is_configured()
+            && match kind {
+                RequestKind::Forecast => capabilities.forecast,
+                RequestKind::Alerts => capabilities.alerts,
+                RequestKind::Weather | RequestKind::Lookup => true,
+            }
+    });
+    let healthy = capable
+        .clone()
+        .find(|index| providers[*index].health.is_available(now));
+    match retry {
+        true => healthy,
+        false => healthy.or_else(|| capable.clone().next()),
+    }
+}
+
+/// The provider to send the failed request to next, if any
+///
+/// Every failure moves on to the next provider, as another one may still serve the weather,
+/// like MetaWeather when the OpenWeather key is wrong. Only faults of the provider itself,
+/// not an unknown city or a rejected key, count against its health.
+fn fail_over(
+    providers: &mut [RegisteredProvider],
+    order: &[usize],
+    attempt: &Attempt,
+    error: &WeatherError,
+    now: DateTime<Utc>,
+) -> Option<usize> {
+    // Alerts need a subscription the key may not have, which says nothing of the provider's health
+    if attempt.kind != RequestKind::Alerts {
+        providers[attempt.provider].health.record_error(error, now);
+    }
+    pick_provider(
+        providers,
+        order,
+        Some(attempt.provider),
+        attempt.kind,
+        true,
+        now,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registered(providers: Vec<Box<dyn WeatherProvider>>) -> Vec<RegisteredProvider> {
+        providers
+            .into_iter()
+            .map(|provider| RegisteredProvider {
+                provider,
+                health: Health::default(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rejected_key_fails_over_without_hurting_health() {
+        let mut open_weather = OpenWeather::default();
+        open_weather.set_api_key(Some("wrong".to_owned()));
+        let mut providers = registered(vec![Box::new(open_weather), Box::new(MetaWeather)]);
+        let attempt = Attempt {
+            location: Location::new("Toronto,CA".to_owned(), 6167865),
+            kind: RequestKind::Weather,
+            provider: 0,
+            retries: 0,
+            first_error: None,
+            coordinates: None,
+        };
+        let now = Utc::now();
+        let rejected = WeatherError::from_status(401, &attempt.location.name);
+        let next = fail_over(&mut providers, &[0, 1], &attempt, &rejected, now);
+        assert_eq!(next, Some(1));
+        assert_eq!(providers[1].provider.name(), MetaWeather.name());
+        assert!(providers[0].health.is_available(now));
+
+        // The second provider answers, and nothing is left to fail over to after it
+        let body = r#"{
+            "title": "Toronto",
+            "consolidated_weather": [{
+                "weather_state_name": "Light Cloud",
+                "weather_state_abbr": "lc",
+                "min_temp": 18.5,
+                "max_temp": 27.1,
+                "the_temp": 25.3,
+                "wind_speed": 10.0,
+                "wind_direction": 240.5,
+                "air_pressure": 1014.5,
+                "humidity": 60,
+                "visibility": 10.0
+            }],
+            "time": "2020-07-13T11:03:42.587430-04:00",
+            "sun_rise": "2020-07-13T05:47:53.345565-04:00",
+            "sun_set": "2020-07-13T20:59:34.957007-04:00"
+        }"#;
+        let weather = providers[1].provider.parse_weather(body).unwrap();
+        assert_eq!(weather.place_name(), Some("Toronto".to_owned()));
+        let second = Attempt {
+            provider: 1,
+            ..attempt
+        };
+        let unavailable = WeatherError::from_status(503, &second.location.name);
+        assert_eq!(
+            fail_over(&mut providers, &[0, 1], &second, &unavailable, now),
+            None
+        );
+        assert!(!providers[1].health.is_available(now));
+    }
 }
@@ -0,0 +1,153 @@
+//! Converts the OpenWeather city list into the binary index searched by the app
+//!
+//! The list is accepted as published by OpenWeather (an array of cities with `id`, `name`,
+//! `state`, `country` and optionally `coord` and `stat.population`),
+//! or as an object of `"Name,CC"` to city id, which has no coordinates nor population
+//! and keeps a single city per name. `build.sh` downloads the OpenWeather list when it is missing.
+//! Cities are named `Name,ST,CC` when they have a state, `Name,CC` otherwise.
+//! Cities without a word in their name, like "-", are left out.
+//!
+//! Usage: `cargo run --bin city_index -- [data/city.list.json] [static/city.index]`
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use weather::{CityIndex, CityRecord, Coordinates};
+
+const DEFAULT_INPUT: &str = "data/city.list.json";
+const DEFAULT_OUTPUT: &str = "static/city.index";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CityList {
+    OpenWeather(Vec<OpenWeatherCity>),
+    Named(BTreeMap<String, u32>),
+}
+
+#[derive(Deserialize)]
+struct OpenWeatherCity {
+    id: u32,
+    name: String,
+    #[serde(default)]
+    state: String,
+    country: String,
+    coord: Option<Coord>,
+    stat: Option<Stat>,
+}
+
+#[derive(Deserialize)]
+struct Coord {
+    lat: f64,
+    lon: f64,
+}
+
+#[derive(Deserialize)]
+struct Stat {
+    population: Option<u32>,
+}
+
+impl OpenWeatherCity {
+    fn display_name(&self) -> String {
+        match self.state.is_empty() {
+            true => format!("{},{}", self.name, self.country),
+            false => format!("{},{},{}", self.name, self.state, self.country),
+        }
+    }
+}
+
+/// Every city of the list, those sharing a name are told apart by their position when suggested
+fn named_cities(list: CityList) -> Vec<CityRecord> {
+    match list {
+        CityList::Named(cities) => cities
+            .into_iter()
+            .map(|(name, id)| CityRecord {
+                name,
+                id,
+                coordinates: None,
+                population: None,
+            })
+            .collect(),
+        CityList::OpenWeather(cities) => cities
+            .into_iter()
+            .map(|city| CityRecord {
+                name: city.display_name(),
+                id: city.id,
+                coordinates: city.coord.map(|coord| Coordinates {
+                    latitude: coord.lat,
+                    longitude: coord.lon,
+                }),
+                population: city.stat.and_then(|stat| stat.population),
+            })
+            .collect(),
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1);
+    let input = args.next().unwrap_or_else(|| DEFAULT_INPUT.to_owned());
+    let output = args.next().unwrap_or_else(|| DEFAULT_OUTPUT.to_owned());
+    let list = fs::read(&input).map_err(|error| format!("Cannot read {}: {}", input, error))?;
+    let list = serde_json::from_slice::<CityList>(&list)
+        .map_err(|error| format!("{} is not a city list: {}", input, error))?;
+    let cities = named_cities(list);
+    let listed = cities.len();
+    let index = CityIndex::build(cities).map_err(|error| error.to_string())?;
+    let indexed = CityIndex::parse(index.clone())
+        .map_err(|error| error.to_string())?
+        .len();
+    fs::write(&output, &index).map_err(|error| format!("Cannot write {}: {}", output, error))?;
+    println!(
+        "Indexed {} of {} cities into {} ({} bytes)",
+        indexed,
+        listed,
+        output,
+        index.len()
+    );
+    Ok(())
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPEN_WEATHER_LIST: &str = r#"[
+        {"id": 4250542, "name": "Springfield", "state": "IL", "country": "US",
+         "coord": {"lon": -89.64371, "lat": 39.80172}, "stat": {"level": 1.0, "population": 116250}},
+        {"id": 4250546, "name": "Springfield", "state": "IL", "country": "US",
+         "coord": {"lon": -88.06, "lat": 41.59}},
+        {"id": 2988507, "name": "Paris", "state": "", "country": "FR",
+         "coord": {"lon": 2.3488, "lat": 48.853409}, "stat": {"population": 2138551}},
+        {"id": 620970, "name": "-", "state": "", "country": "BY",
+         "coord": {"lon": 28.0, "lat": 53.0}}
+    ]"#;
+
+    #[test]
+    fn every_city_of_the_open_weather_list_is_indexed() {
+        let list = serde_json::from_str::<CityList>(OPEN_WEATHER_LIST).unwrap();
+        let cities = named_cities(list);
+        assert_eq!(cities.len(), 4);
+        let index = CityIndex::parse(CityIndex::build(cities).unwrap()).unwrap();
+        assert_eq!(index.len(), 3);
+
+        let springfields = index.starting_with("springfield");
+        assert_eq!(springfields.len(), 2);
+        let mut found = springfields
+            .iter()
+            .map(|city| (index.id(*city), index.population(*city)))
+            .collect::<Vec<_>>();
+        found.sort();
+        assert_eq!(found, vec![(4250542, Some(116_250)), (4250546, None)]);
+
+        let paris = *index.starting_with("paris").iter().next().unwrap();
+        assert_eq!(index.name(paris), "Paris,FR");
+        let at = index.coordinates(paris).unwrap();
+        assert!((at.latitude - 48.85341).abs() < 1e-5 && (at.longitude - 2.3488).abs() < 1e-5);
+    }
+}
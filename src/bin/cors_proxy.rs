@@ -0,0 +1,188 @@
+//! A small CORS proxy for providers without CORS support, e.g. MetaWeather
+//!
+//! Both proxy styles are accepted:
+//! - prefix: `http://localhost:8080/https://www.metaweather.com/api/location/4118`
+//! - query: `http://localhost:8080/?url=https%3A%2F%2Fwww.metaweather.com%2Fapi%2Flocation%2F4118`
+//!
+//! With `--fixtures <dir>`, responses are saved into the directory and
+//! served from it when the upstream cannot be reached, which allows testing offline.
+//!
+//! Usage: `cargo run --bin cors_proxy -- [--port 8080] [--fixtures <dir>]`
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tiny_http::{Header, Method, Request, Response, Server};
+use url::Url;
+
+const DEFAULT_PORT: u16 = 8080;
+
+struct Options {
+    port: u16,
+    fixtures: Option<PathBuf>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        port: DEFAULT_PORT,
+        fixtures: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                options.port = args
+                    .next()
+                    .and_then(|port| port.parse().ok())
+                    .ok_or("--port expects a port number")?
+            }
+            "--fixtures" => {
+                options.fixtures = Some(args.next().ok_or("--fixtures expects a directory")?.into())
+            }
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+/// Extracts the proxied url from either `/<url>` or `/?url=<url>`
+fn target_url(request_url: &str) -> Option<Url> {
+    let base = Url::parse("http://proxy").ok()?;
+    let request = base.join(request_url).ok()?;
+    let target = request
+        .query_pairs()
+        .find(|(key, _)| key == "url")
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_else(|| request_url.trim_start_matches('/').to_owned());
+    Url::parse(&target)
+        .ok()
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+}
+
+fn fixture_path(fixtures: &Path, url: &Url) -> PathBuf {
+    let name = url
+        .as_str()
+        .chars()
+        .map(|char| match char {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => char,
+            _ => '_',
+        })
+        .collect::<String>();
+    fixtures.join(name)
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("Invalid header")
+}
+
+fn respond(request: Request, status: u16, body: Vec<u8>, content_type: &str) {
+    let response = Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header("Access-Control-Allow-Origin", "*"))
+        .with_header(header("Content-Type", content_type));
+    if let Err(error) = request.respond(response) {
+        eprintln!("Failed to respond: {}", error);
+    }
+}
+
+/// Fetches the url, returning the status, body and content type
+fn fetch_upstream(url: &Url) -> Result<(u16, Vec<u8>, String), String> {
+    let response = match ureq::get(url.as_str()).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(error) => return Err(error.to_string()),
+    };
+    let status = response.status();
+    let content_type = response.content_type().to_owned();
+    let mut body = vec![];
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|error| error.to_string())?;
+    Ok((status, body, content_type))
+}
+
+fn handle(request: Request, fixtures: Option<&Path>) {
+    if request.method() == &Method::Options {
+        let response = Response::empty(204)
+            .with_header(header("Access-Control-Allow-Origin", "*"))
+            .with_header(header("Access-Control-Allow-Methods", "GET, OPTIONS"))
+            .with_header(header("Access-Control-Allow-Headers", "*"));
+        let _ = request.respond(response);
+        return;
+    }
+    let url = match target_url(request.url()) {
+        Some(url) => url,
+        None => {
+            let message = format!("Not a proxied url: {}", request.url());
+            return respond(request, 400, message.into_bytes(), "text/plain");
+        }
+    };
+    match fetch_upstream(&url) {
+        Ok((status, body, content_type)) => {
+            println!("{} {}", status, url);
+            if let (Some(fixtures), 200) = (fixtures, status) {
+                if let Err(error) = fs::write(fixture_path(fixtures, &url), &body) {
+                    eprintln!("Failed to save fixture for {}: {}", url, error);
+                }
+            }
+            respond(request, status, body, &content_type)
+        }
+        Err(error) => {
+            let fixture = fixtures.and_then(|fixtures| fs::read(fixture_path(fixtures, &url)).ok());
+            match fixture {
+                Some(body) => {
+                    println!("fixture {}", url);
+                    respond(request, 200, body, "application/json")
+                }
+                None => {
+                    eprintln!("502 {}: {}", url, error);
+                    respond(request, 502, error.into_bytes(), "text/plain")
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            eprintln!("Usage: cors_proxy [--port 8080] [--fixtures <dir>]");
+            std::process::exit(2);
+        }
+    };
+    if let Some(fixtures) = options.fixtures.as_ref() {
+        fs::create_dir_all(fixtures).expect("Failed to create fixtures directory");
+    }
+    let server = Server::http(("127.0.0.1", options.port)).expect("Failed to start proxy");
+    println!(
+        "Proxying on http://localhost:{0}/?url={{url}} and http://localhost:{0}/",
+        options.port
+    );
+    for request in server.incoming_requests() {
+        handle(request, options.fixtures.as_deref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_prefix_and_query_style() {
+        let expected = "https://www.metaweather.com/api/location/search/?query=s%C3%A3o";
+        assert_eq!(
+            target_url(&format!("/{}", expected)).unwrap().as_str(),
+            expected
+        );
+        assert_eq!(
+            target_url("/?url=https%3A%2F%2Fwww.metaweather.com%2Fapi%2Flocation%2Fsearch%2F%3Fquery%3Ds%25C3%25A3o")
+                .unwrap()
+                .as_str(),
+            expected
+        );
+        assert!(target_url("/favicon.ico").is_none());
+    }
+}
@@ -5,11 +5,21 @@ mod model;
 mod util;
 mod views;
 use wasm_bindgen::prelude::*;
+use yew::agent::Threaded;
 use yew::App;
 
+/// Used by the `city_index` binary to build the index
+pub use api::locations::{CityIndex, CityRecord, Coordinates};
+
 #[wasm_bindgen(start)]
 pub fn start() {
-    App::<model::Model>::new().mount_to_body();
+    match web_sys::window() {
+        Some(_) => {
+            App::<model::Model>::new().mount_to_body();
+        }
+        // Loaded by `LocationAgent` into its web worker
+        None => <api::locations::LocationAgent as Threaded>::register(),
+    }
 }
 
 #[cfg(test)]
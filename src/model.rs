@@ -1,69 +1,223 @@
-use crate::api::locations::Storage;
-use crate::api::{LocationWeather, WeatherAPI};
-use crate::views::{View, WeatherBoard};
+use crate::api::config::{Config, ConfigLoader};
+use crate::api::locations::{
+    Coordinates, Geolocation, Location, LocationAgent, LocationRequest, LocationResponse, Place,
+    Search,
+};
+use crate::api::{
+    Fetched, ForecastWeather, LocationWeather, Origin, PersistedState, Proxy, Reply, Settings,
+    WeatherAPI, WeatherAlerts, WeatherError, DEFAULT_REFRESH_MINUTES, DEFAULT_TIMEOUT_SECONDS,
+    DEFAULT_TTL_MINUTES,
+};
+use crate::util::network::{self, NetworkListener};
+use crate::util::url;
+use crate::util::visibility::{self, VisibilityListener};
+use crate::views::{
+    AlertBanner, ErrorBanner, OfflineNotice, PageControl, RetryNotice, SettingsDialog,
+    SettingsEdit, View, WeatherBoard,
+};
 use anyhow::Error;
+use chrono::Utc;
+use std::time::Duration;
 use wasm_bindgen::JsCast;
-use web_sys::HtmlMediaElement;
+use web_sys::{HtmlMediaElement, Url};
+use yew::agent::{Bridge, Bridged};
 use yew::format::Json;
 use yew::services::console::ConsoleService;
 use yew::services::fetch::Response;
+use yew::services::keyboard::{KeyListenerHandle, KeyboardService};
 use yew::services::storage::Area;
 use yew::services::StorageService;
 use yew::utils;
-use yew::{html, Component, ComponentLink, Html, InputData, KeyboardEvent};
+use yew::{html, Component, ComponentLink, Html, InputData, KeyboardEvent, TouchEvent};
 
 pub struct Model {
     link: ComponentLink<Self>,
     console: ConsoleService,
     storage: Option<StorageService>,
     weather_api: WeatherAPI,
-    location_api: Storage,
-    view: Option<WeatherBoard>,
-    suggestions: Vec<(String, usize)>,
+    location_agent: Box<dyn Bridge<LocationAgent>>,
+    geolocation: Geolocation,
+    config_loader: ConfigLoader,
+    /// Deployment defaults for the settings not set by the user
+    config: Config,
+    state: PersistedState,
+    settings_draft: Settings,
+    settings_open: bool,
+    pages: Vec<Page>,
+    current_page: usize,
+    swipe_start: Option<i32>,
+    _key_listener: KeyListenerHandle,
+    _visibility_listener: VisibilityListener,
+    /// Whether the browser is online, weather is only requested while it is
+    online: bool,
+    _network_listener: NetworkListener,
+    search_query: String,
+    suggestions: Vec<Location>,
+    /// Matches of the search query not listed in the suggestions
+    more_suggestions: usize,
+    suggestion_limit: usize,
+    /// Suggestion picked by pressing enter, moved with the arrow keys
+    highlighted: Option<usize>,
+    /// Keys of the alerts dismissed since launch, kept hidden when refreshed
+    dismissed_alerts: Vec<String>,
+}
+
+/// A saved city, with its weather once received
+struct Page {
+    location: Location,
+    board: Option<WeatherBoard>,
+    error: Option<WeatherError>,
+}
+
+impl Page {
+    fn new(location: Location) -> Self {
+        Page {
+            location,
+            board: None,
+            error: None,
+        }
+    }
 }
 
 pub enum Msg {
     LoadLocation,
     LoadSearchBar,
-    LoadWeather(String, usize),
-    Fetched(String, LocationWeather),
+    AddLocation(Location),
+    Located(Option<Coordinates>),
+    RemoveLocation,
+    MoveLocation(isize),
+    ShowPage(usize),
+    TurnPage(isize),
+    SwipeStart(i32),
+    SwipeEnd(i32),
+    Refresh,
+    RefreshAll,
+    AutoRefresh,
+    VisibilityChanged(bool),
+    NetworkChanged(bool),
+    Received(Reply),
+    ForecastReceived(Reply),
+    AlertsReceived(Reply),
+    DismissAlert(String),
     Search(String),
-    Failed(String),
-    LocationDataLoaded(Vec<u8>),
+    MoreSuggestions,
+    HighlightSuggestion(isize),
+    PickSuggestion,
+    CloseSuggestions,
+    ConfigLoaded(Config),
+    EditSettings(SettingsEdit),
+    SaveSettings,
+    ToggleSettings,
+    DismissError,
+    LocationsFound(LocationResponse),
     PlayVideo,
     Shade,
     Ignored,
 }
 
-static LOCATION_KEY: &str = "location";
+static DEFAULT_LOCATION: (&str, usize) = ("Toronto", 6167865);
+static API_KEY_PARAMETER: &str = "apikey";
+static PROXY_PARAMETER: &str = "proxy";
+/// Horizontal distance in pixels a touch has to travel to turn the page
+const SWIPE_DISTANCE: i32 = 50;
+/// Built by the `city_index` binary
+static CITY_INDEX: &str = "city.index";
+/// Suggestions listed at first, and added each time more are asked for
+const SUGGESTION_LIMIT: usize = 10;
+/// Cities picked from the search bar remembered to rank them first
+const RECENT_CITIES: usize = 10;
 
 impl Component for Model {
     type Message = Msg;
     type Properties = ();
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut storage = StorageService::new(Area::Local).ok();
+        let (state, warning) = storage
+            .as_mut()
+            .map(PersistedState::restore)
+            .unwrap_or_default();
+        let mut console = ConsoleService::new();
+        if let Some(warning) = warning {
+            console.warn(&warning);
+        }
         let mut model = Model {
-            link,
-            console: ConsoleService::new(),
-            storage: StorageService::new(Area::Local).ok(),
+            link: link.clone(),
+            console,
+            storage,
             weather_api: WeatherAPI::new(),
-            location_api: Storage::new(),
-            view: None,
+            location_agent: LocationAgent::bridge(link.callback(Msg::LocationsFound)),
+            geolocation: Geolocation::new(),
+            config_loader: ConfigLoader::new(),
+            config: Config::default(),
+            settings_draft: state.settings.clone(),
+            pages: state.locations.iter().cloned().map(Page::new).collect(),
+            state,
+            settings_open: false,
+            current_page: 0,
+            swipe_start: None,
+            _key_listener: KeyboardService::register_key_down(
+                &utils::window(),
+                link.callback(Self::key_down),
+            ),
+            _visibility_listener: VisibilityListener::new(link.callback(Msg::VisibilityChanged)),
+            online: network::is_online(),
+            _network_listener: NetworkListener::new(link.callback(Msg::NetworkChanged)),
+            search_query: String::new(),
             suggestions: vec![],
+            more_suggestions: 0,
+            suggestion_limit: SUGGESTION_LIMIT,
+            highlighted: None,
+            dismissed_alerts: vec![],
         };
-        model.update(Msg::LoadLocation);
+        model.load_config();
         model
     }
 
     fn update(&mut self, msg: Self::Message) -> bool {
         match msg {
             Msg::LoadLocation => self.load_location(),
-            Msg::LoadWeather(title, id) => return self.load_weather(title, id),
-            Msg::Fetched(location, response) => return self.display_weather(location, response),
-            Msg::Search(city) => return self.search_city(&city),
+            Msg::AddLocation(location) => return self.add_location(location),
+            Msg::Located(coordinates) => return self.located(coordinates),
+            Msg::RemoveLocation => return self.remove_location(),
+            Msg::MoveLocation(offset) => return self.move_location(offset),
+            Msg::ShowPage(index) => return self.show_page(index),
+            Msg::TurnPage(offset) => return self.turn_page(offset),
+            Msg::SwipeStart(x) => self.swipe_start = Some(x),
+            Msg::SwipeEnd(x) => return self.swipe_end(x),
+            Msg::Refresh => return self.refresh(),
+            Msg::RefreshAll => return self.refresh_all(),
+            Msg::AutoRefresh => return self.refresh_all(),
+            Msg::VisibilityChanged(visible) => return self.visibility_changed(visible),
+            Msg::NetworkChanged(online) => return self.network_changed(online),
+            // A newer request was sent for the location since, or it was removed
+            Msg::Received(reply) | Msg::ForecastReceived(reply) | Msg::AlertsReceived(reply)
+                if !self.weather_api.is_current(&reply) => {}
+            Msg::Received(reply) => return self.receive(reply),
+            Msg::ForecastReceived(reply) => return self.receive_forecast(reply),
+            Msg::AlertsReceived(reply) => return self.receive_alerts(reply),
+            Msg::DismissAlert(key) => return self.dismiss_alert(key),
+            Msg::Search(city) => return self.search_city(city),
+            Msg::MoreSuggestions => {
+                self.suggestion_limit += SUGGESTION_LIMIT;
+                return self.search_city(self.search_query.clone());
+            }
+            Msg::HighlightSuggestion(offset) => return self.highlight_suggestion(offset),
+            Msg::PickSuggestion => return self.pick_suggestion(),
+            Msg::CloseSuggestions => return self.close_suggestions(),
             Msg::LoadSearchBar => self.load_search_bar(),
-            Msg::LocationDataLoaded(bytes) => self.populate_location_storage(bytes),
-            Msg::Failed(info) => self.console.error(&info),
+            Msg::LocationsFound(response) => return self.locations_found(response),
+            Msg::ConfigLoaded(config) => return self.config_loaded(config),
+            Msg::EditSettings(edit) => self.edit_settings(edit),
+            Msg::SaveSettings => return self.save_settings(),
+            Msg::ToggleSettings => return self.toggle_settings(),
+            Msg::DismissError => {
+                return self
+                    .pages
+                    .get_mut(self.current_page)
+                    .and_then(|page| page.error.take())
+                    .is_some()
+            }
             Msg::PlayVideo => self.play_video(),
             Msg::Shade => return self.shade_views(),
             Msg::Ignored => (),
@@ -76,30 +230,115 @@ impl Component for Model {
     }
 
     fn view(&self) -> Html {
-        let list = self.suggestions.clone().into_iter().map(move |(title, id)| {
-            let cloned_title = title.clone();
-            html! {
-                <li onclick=self.link.callback_once(move |_| Msg::LoadWeather(cloned_title, id))>{ &title }</li>
-            }
-        });
+        let labels = Location::labels(&self.suggestions);
+        let list = self
+            .suggestions
+            .clone()
+            .into_iter()
+            .zip(labels)
+            .enumerate()
+            .map(move |(index, (location, label))| {
+                let flag = location.place.as_ref().map(Place::flag).unwrap_or_default();
+                let highlighted = self.highlighted == Some(index);
+                html! {
+                    <li id=suggestion_id(index)
+                        role="option"
+                        aria-selected=highlighted.to_string()
+                        class=if highlighted { "highlighted" } else { "" }
+                        onclick=self.link.callback_once(move |_| Msg::AddLocation(location))>
+                        <span class="flag" aria-hidden="true">{ flag }</span>{ label }
+                    </li>
+                }
+            });
+        let page = self.pages.get(self.current_page);
         html! {
         <>
+            <div id="page"
+                ontouchstart=self.link.callback(|e: TouchEvent| Msg::SwipeStart(touch_x(&e)))
+                ontouchend=self.link.callback(|e: TouchEvent| Msg::SwipeEnd(touch_x(&e)))>
             {
-                self.view.as_ref()
+                page.and_then(|page| page.board.as_ref())
                     .map(|board| board.display())
                     .unwrap_or(html!{})
             }
+            </div>
+            {
+                if self.pages.len() > 1 {
+                    PageControl::new(
+                        self.pages.iter().map(|page| page.location.name.clone()).collect(),
+                        self.current_page,
+                        self.link.callback(Msg::ShowPage),
+                        self.link.callback(Msg::MoveLocation),
+                        self.link.callback(|_| Msg::RemoveLocation),
+                    )
+                    .display()
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if self.online {
+                    html! {}
+                } else {
+                    OfflineNotice::new(page.and_then(|page| page.board.as_ref()).map(WeatherBoard::updated))
+                        .display()
+                }
+            }
+            {
+                page.and_then(|page| self.weather_api.retries(&page.location))
+                    .map(|retries| RetryNotice::new(retries).display())
+                    .unwrap_or(html!{})
+            }
+            {
+                page.and_then(|page| page.error.as_ref())
+                    .map(|error| ErrorBanner::new(
+                        error.clone(),
+                        self.link.callback(|_| Msg::Refresh),
+                        self.link.callback(|_| Msg::ToggleSettings),
+                        self.link.callback(|_| Msg::DismissError),
+                    ).display())
+                    .unwrap_or(html!{})
+            }
+            {
+                if self.settings_open {
+                    SettingsDialog::new(
+                        self.settings_draft.clone(),
+                        self.weather_api.provider_names(),
+                        self.api_key().is_none(),
+                        self.link.callback(Msg::EditSettings),
+                        self.link.callback(|_| Msg::SaveSettings),
+                        self.link.callback(|_| Msg::ToggleSettings),
+                    )
+                    .display()
+                } else {
+                    html! {}
+                }
+            }
             <div id="searchBarArea">
                 <input id="searchBar"
                     placeholder="Find your city here"
-                    onclick=self.link.callback(|_| Msg::LoadSearchBar)
+                    onfocus=self.link.callback(|_| Msg::LoadSearchBar)
+                    role="combobox"
+                    aria-autocomplete="list"
+                    aria-controls="suggestions"
+                    aria-expanded=(!self.suggestions.is_empty()).to_string()
+                    aria-activedescendant=self.highlighted.map(suggestion_id).unwrap_or_default()
                     oninput=self.link.callback(|e: InputData| Msg::Search(e.value))
-                    onkeypress=self.link.callback(move |e: KeyboardEvent| {
-                        Msg::Ignored
-                    })
+                    onkeydown=self.link.callback(Self::search_key_down)
                 />
-                <ul id="suggestions">
+                <ul id="suggestions" role="listbox" aria-label="Cities">
                 { for list }
+                {
+                    if self.more_suggestions > 0 {
+                        html! {
+                            <li class="more" onclick=self.link.callback(|_| Msg::MoreSuggestions)>
+                                { format!("{} more results…", self.more_suggestions) }
+                            </li>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
                 </ul>
             </div>
         </>
@@ -110,7 +349,7 @@ impl Component for Model {
 impl Model {
     fn shade_views(&mut self) -> bool {
         let class = "shaded";
-        let targeted_ids = ["shade", "today"];
+        let targeted_ids = ["shade", "today", "chart", "forecast"];
         for id in &targeted_ids {
             if let Some(element) = utils::document().get_element_by_id(id) {
                 let class_list = element.class_list();
@@ -135,16 +374,112 @@ impl Model {
         }
     }
 
-    fn search_city(&mut self, city: &str) -> bool {
+    fn search_city(&mut self, city: String) -> bool {
+        if city != self.search_query {
+            self.suggestion_limit = SUGGESTION_LIMIT;
+            self.highlighted = None;
+        }
         if city.len() < 3 {
             self.suggestions.clear();
+            self.more_suggestions = 0;
         } else {
-            self.suggestions = self.location_api.find(&city).into_iter().collect();
+            self.location_agent.send(LocationRequest::Search(Search {
+                query: city.clone(),
+                limit: self.suggestion_limit,
+                recent: self.state.recent_cities.clone(),
+            }));
         }
+        self.search_query = city;
         true
     }
 
-    fn display_weather(&mut self, title: String, weather: LocationWeather) -> bool {
+    fn receive(&mut self, reply: Reply) -> bool {
+        let key = reply.location().key();
+        match self
+            .weather_api
+            .receive(reply, self.link.callback(Msg::Received))
+        {
+            Ok(Some(fetched)) => self.display_fetched(&key, fetched),
+            // Shows whether the weather is being retried
+            Ok(None) => self.page_index(&key) == Some(self.current_page),
+            Err(error) => self.display_error(&key, error),
+        }
+    }
+
+    fn receive_forecast(&mut self, reply: Reply) -> bool {
+        match self
+            .weather_api
+            .receive(reply, self.link.callback(Msg::ForecastReceived))
+        {
+            Ok(Some(Fetched::Forecast(location, forecast))) => {
+                self.display_forecast(location, forecast)
+            }
+            Ok(_) => false,
+            Err(error) => {
+                self.console
+                    .error(format!("Error for requesting forecast: {}", error).as_str());
+                false
+            }
+        }
+    }
+
+    fn receive_alerts(&mut self, reply: Reply) -> bool {
+        match self
+            .weather_api
+            .receive(reply, self.link.callback(Msg::AlertsReceived))
+        {
+            Ok(Some(Fetched::Alerts(location, alerts))) => self.display_alerts(location, alerts),
+            Ok(_) => false,
+            Err(error) => {
+                self.console
+                    .error(format!("Error for requesting alerts: {}", error).as_str());
+                false
+            }
+        }
+    }
+
+    fn page_index(&self, key: &str) -> Option<usize> {
+        self.pages
+            .iter()
+            .position(|page| page.location.key() == key)
+    }
+
+    fn display_error(&mut self, key: &str, error: WeatherError) -> bool {
+        self.console.error(&format!("{}", error));
+        match self.page_index(key) {
+            Some(index) => {
+                self.pages[index].error.replace(error);
+                index == self.current_page
+            }
+            None => false,
+        }
+    }
+
+    fn display_fetched(&mut self, key: &str, fetched: Fetched) -> bool {
+        match fetched {
+            Fetched::Weather(location, weather, origin) => {
+                self.display_weather(key, location, *weather, origin)
+            }
+            Fetched::Forecast(location, forecast) => self.display_forecast(location, forecast),
+            Fetched::Alerts(location, alerts) => self.display_alerts(location, alerts),
+        }
+    }
+
+    fn display_weather(
+        &mut self,
+        key: &str,
+        location: Location,
+        weather: LocationWeather,
+        origin: Origin,
+    ) -> bool {
+        let coordinates = location
+            .coordinates
+            .or_else(|| location.place.as_ref().and_then(|place| place.coordinates))
+            .or_else(|| weather.coordinates());
+        let index = match self.page_index(key) {
+            Some(index) => index,
+            None => return false,
+        };
         let window = utils::window();
         let portrait = match (window.inner_height(), window.inner_width()) {
             (Ok(height), Ok(width)) => {
@@ -152,90 +487,575 @@ impl Model {
             }
             _ => false,
         };
-        self.view.replace(WeatherBoard::new(
-            title,
+        let board = WeatherBoard::new(
+            location.name.clone(),
             weather,
+            origin,
             portrait,
-            self.link.callback(|_| Msg::LoadLocation),
+            self.link.callback(|_| Msg::Refresh),
             self.link.callback(|_| Msg::Shade),
-        ));
-        self.link.send_message(Msg::PlayVideo);
-        return true;
+            self.link.callback(|_| Msg::ToggleSettings),
+        );
+        let page = &mut self.pages[index];
+        match board {
+            Ok(mut board) => {
+                board.set_units(self.state.settings.units);
+                page.board.replace(board)
+            }
+            Err(error) => return self.display_error(key, error),
+        };
+        page.error = None;
+        page.location = location.clone();
+        self.state.last_refresh = self.state.last_refresh.max(Some(origin.updated));
+        self.persist();
+        if index == self.current_page {
+            self.link.send_message(Msg::PlayVideo);
+        }
+        self.load_forecast(location.clone());
+        if let Some(coordinates) = coordinates {
+            self.load_alerts(location, coordinates);
+        }
+        index == self.current_page
     }
 
-    fn load_weather(&mut self, city_name: String, id: usize) -> bool {
-        self.suggestions.clear();
+    fn display_forecast(&mut self, location: Location, forecast: ForecastWeather) -> bool {
+        let index = self.page_index(&location.key());
+        match index.and_then(|index| self.pages[index].board.as_mut()) {
+            Some(board) => {
+                board.show_forecast(forecast);
+                index == Some(self.current_page)
+            }
+            None => false,
+        }
+    }
+
+    fn display_alerts(&mut self, location: Location, alerts: WeatherAlerts) -> bool {
+        let banner = AlertBanner::new(
+            &alerts,
+            Utc::now().timestamp(),
+            &self.dismissed_alerts,
+            self.link.callback(Msg::DismissAlert),
+        );
+        let index = self.page_index(&location.key());
+        match index.and_then(|index| self.pages[index].board.as_mut()) {
+            Some(board) => {
+                board.show_alerts(banner);
+                index == Some(self.current_page)
+            }
+            None => false,
+        }
+    }
+
+    /// Hides the alert on every city it was issued for
+    fn dismiss_alert(&mut self, key: String) -> bool {
+        for board in self.pages.iter_mut().filter_map(|page| page.board.as_mut()) {
+            board.dismiss_alert(&key);
+        }
+        self.dismissed_alerts.push(key);
+        true
+    }
+
+    /// Remembers the cities and settings for the next launch
+    fn persist(&mut self) {
+        self.state.locations = self
+            .pages
+            .iter()
+            .map(|page| page.location.clone())
+            .collect();
         if let Some(storage) = self.storage.as_mut() {
-            storage.store(LOCATION_KEY, Ok(format!("{}|{}", city_name, id)));
-        }
-        let res = self.weather_api.fetch(
-            id,
-            self.link.callback_once(
-                move |response: Response<Json<Result<LocationWeather, Error>>>| {
-                    let (meta, Json(res)) = response.into_parts();
-                    if meta.status.is_success() {
-                        match res {
-                            Ok(body) => Msg::Fetched(city_name, body),
-                            Err(error) => Msg::Failed(format!("{}", error)),
-                        }
-                    } else {
-                        Msg::Failed("Response failed".to_owned())
-                    }
-                },
-            ),
+            self.state.store(storage);
+        }
+    }
+
+    /// Shows the cached weather right away, and requests it again unless it is fresh or offline
+    fn load_weather(&mut self, location: Location) {
+        let key = location.key();
+        if let Some(cached) = self.weather_api.cached_weather(&location) {
+            self.display_fetched(&key, cached.fetched);
+            if cached.fresh {
+                return;
+            }
+        }
+        if !self.online {
+            return;
+        }
+        let res = self
+            .weather_api
+            .fetch(location, self.link.callback(Msg::Received));
+        if let Err(error) = res {
+            self.display_error(&key, error);
+        }
+    }
+
+    fn load_forecast(&mut self, location: Location) {
+        if let Some(cached) = self.weather_api.cached_forecast(&location) {
+            self.display_fetched(&location.key(), cached.fetched);
+            if cached.fresh {
+                return;
+            }
+        }
+        if !self.online {
+            return;
+        }
+        let res = self
+            .weather_api
+            .fetch_forecast(location, self.link.callback(Msg::ForecastReceived));
+        if let Err(error) = res {
+            self.console
+                .error(format!("Error for requesting forecast: {}", error).as_str());
+        }
+    }
+
+    /// Shows the cached alerts right away, and requests them again unless they are fresh or offline
+    fn load_alerts(&mut self, location: Location, coordinates: Coordinates) {
+        if let Some(cached) = self.weather_api.cached_alerts(&location) {
+            self.display_fetched(&location.key(), cached.fetched);
+            if cached.fresh {
+                return;
+            }
+        }
+        if !self.online {
+            return;
+        }
+        let res = self.weather_api.fetch_alerts(
+            location,
+            coordinates,
+            self.link.callback(Msg::AlertsReceived),
         );
         if let Err(error) = res {
             self.console
-                .error(format!("Error for requesting weather: {}", error).as_str());
+                .error(format!("Error for requesting alerts: {}", error).as_str());
         }
-        return true;
     }
 
-    fn load_location(&mut self) {
-        // load location
-        let mut msg_title = String::from("Toronto");
-        let mut msg_id = 6167865_usize;
-        if let Some(location) = self
-            .storage
-            .as_ref()
-            .and_then(|storage| storage.restore::<Result<String, Error>>(LOCATION_KEY).ok())
+    /// Shows the city, adding it to the saved cities if it is new
+    fn add_location(&mut self, location: Location) -> bool {
+        self.close_suggestions();
+        if let Some(id) = location.id {
+            let recent = &mut self.state.recent_cities;
+            recent.retain(|recent_id| *recent_id != id);
+            recent.insert(0, id);
+            recent.truncate(RECENT_CITIES);
+        }
+        let index = match self
+            .pages
+            .iter()
+            .position(|page| page.location.id.is_some() && page.location.id == location.id)
         {
-            let mut split = location.splitn(2, "|");
-            let title = split.next();
-            let id = split.next().and_then(|id| id.parse::<usize>().ok());
-            match (title, id) {
-                (Some(title), Some(id)) => {
-                    msg_title = title.to_owned();
-                    msg_id = id;
-                }
-                _ => (),
+            Some(index) => index,
+            None => {
+                self.pages.push(Page::new(location));
+                self.pages.len() - 1
             }
+        };
+        self.persist();
+        self.current_page = index;
+        self.refresh()
+    }
+
+    fn remove_location(&mut self) -> bool {
+        if self.pages.len() < 2 {
+            return false;
+        }
+        let page = self.pages.remove(self.current_page);
+        self.weather_api.forget(&page.location);
+        self.current_page = self.current_page.min(self.pages.len() - 1);
+        self.persist();
+        self.link.send_message(Msg::PlayVideo);
+        true
+    }
+
+    /// Moves the current city by `offset` in the saved cities
+    fn move_location(&mut self, offset: isize) -> bool {
+        let target = self.current_page as isize + offset;
+        if target < 0 || target as usize >= self.pages.len() {
+            return false;
         }
-        self.link.send_message(Msg::LoadWeather(msg_title, msg_id));
+        self.pages.swap(self.current_page, target as usize);
+        self.current_page = target as usize;
+        self.persist();
+        true
     }
 
-    fn load_search_bar(&mut self) {
-        if self.location_api.populated {
-            return;
+    fn show_page(&mut self, index: usize) -> bool {
+        if index >= self.pages.len() || index == self.current_page {
+            return false;
         }
-        self.location_api.load(self.link.callback_once(
-            |response: Response<Result<Vec<u8>, Error>>| {
-                let (meta, data) = response.into_parts();
-                if meta.status.is_success() {
-                    match data {
-                        Ok(bytes) => Msg::LocationDataLoaded(bytes),
-                        Err(error) => {
-                            Msg::Failed(format!("Location data is not correct: {}", error))
-                        }
-                    }
-                } else {
-                    Msg::Failed("Location request failed".to_owned())
+        self.current_page = index;
+        self.link.send_message(Msg::PlayVideo);
+        true
+    }
+
+    fn turn_page(&mut self, offset: isize) -> bool {
+        let target = self.current_page as isize + offset;
+        if target < 0 {
+            return false;
+        }
+        self.show_page(target as usize)
+    }
+
+    fn swipe_end(&mut self, x: i32) -> bool {
+        match self.swipe_start.take().map(|start| x - start) {
+            Some(distance) if distance > SWIPE_DISTANCE => self.turn_page(-1),
+            Some(distance) if distance < -SWIPE_DISTANCE => self.turn_page(1),
+            _ => false,
+        }
+    }
+
+    /// Up and down arrows move through the suggestions, Enter picks one and Escape closes them
+    fn search_key_down(event: KeyboardEvent) -> Msg {
+        let msg = match event.key().as_str() {
+            "ArrowDown" => Msg::HighlightSuggestion(1),
+            "ArrowUp" => Msg::HighlightSuggestion(-1),
+            "Enter" => Msg::PickSuggestion,
+            "Escape" => Msg::CloseSuggestions,
+            _ => return Msg::Ignored,
+        };
+        // Keeps the cursor in place instead of jumping to either end of the input
+        event.prevent_default();
+        msg
+    }
+
+    fn highlight_suggestion(&mut self, offset: isize) -> bool {
+        let count = self.suggestions.len() as isize;
+        if count == 0 {
+            return false;
+        }
+        let index = match self.highlighted {
+            Some(index) => (index as isize + offset).rem_euclid(count),
+            None if offset > 0 => 0,
+            None => count - 1,
+        };
+        self.highlighted = Some(index as usize);
+        true
+    }
+
+    /// Shows the highlighted city, or the best match if none is highlighted
+    fn pick_suggestion(&mut self) -> bool {
+        match self
+            .suggestions
+            .get(self.highlighted.unwrap_or_default())
+            .cloned()
+        {
+            Some(location) => self.add_location(location),
+            None => false,
+        }
+    }
+
+    fn close_suggestions(&mut self) -> bool {
+        self.suggestions.clear();
+        self.more_suggestions = 0;
+        self.highlighted = None;
+        true
+    }
+
+    /// Arrow keys turn the page, unless the user is typing
+    fn key_down(event: KeyboardEvent) -> Msg {
+        let typing = utils::document()
+            .active_element()
+            .map(|element| element.tag_name() == "INPUT")
+            .unwrap_or_default();
+        match event.key().as_str() {
+            "ArrowLeft" if !typing => Msg::TurnPage(-1),
+            "ArrowRight" if !typing => Msg::TurnPage(1),
+            _ => Msg::Ignored,
+        }
+    }
+
+    fn refresh(&mut self) -> bool {
+        if let Some(page) = self.pages.get_mut(self.current_page) {
+            page.error = None;
+            let location = page.location.clone();
+            self.load_weather(location);
+        }
+        true
+    }
+
+    /// Fetches the weather of every saved city at once
+    fn refresh_all(&mut self) -> bool {
+        let locations = self
+            .pages
+            .iter_mut()
+            .map(|page| {
+                page.error = None;
+                page.location.clone()
+            })
+            .collect::<Vec<_>>();
+        for location in locations {
+            self.load_weather(location);
+        }
+        true
+    }
+
+    /// Time between automatic refreshes, `None` if they are turned off
+    fn refresh_interval(&self) -> Option<Duration> {
+        match self
+            .state
+            .settings
+            .refresh_minutes
+            .unwrap_or(DEFAULT_REFRESH_MINUTES)
+        {
+            0 => None,
+            minutes => Some(Duration::from_secs(u64::from(minutes) * 60)),
+        }
+    }
+
+    /// Restarts the refresh timer, which only runs while the page is visible
+    fn schedule_refresh(&mut self) {
+        let interval = self.refresh_interval().filter(|_| visibility::is_visible());
+        self.weather_api
+            .schedule_refresh(interval, self.link.callback(|_| Msg::AutoRefresh));
+    }
+
+    /// Shows the offline notice, and catches up on the weather missed once back online
+    fn network_changed(&mut self, online: bool) -> bool {
+        self.online = online;
+        match online {
+            true => self.refresh_all(),
+            false => true,
+        }
+    }
+
+    /// Stops refreshing a hidden page, and catches up on a page shown again after the interval
+    fn visibility_changed(&mut self, visible: bool) -> bool {
+        self.schedule_refresh();
+        let interval = match self.refresh_interval() {
+            Some(interval) if visible => interval.as_secs() as i64,
+            _ => return false,
+        };
+        let stale = self.state.last_refresh.map_or(true, |last_refresh| {
+            Utc::now().timestamp() - last_refresh >= interval
+        });
+        stale && self.refresh_all()
+    }
+
+    fn load_config(&mut self) {
+        self.config_loader.load(self.link.callback_once(
+            |response: Response<Json<Result<Config, Error>>>| {
+                let (meta, Json(config)) = response.into_parts();
+                match (meta.status.is_success(), config) {
+                    (true, Ok(config)) => Msg::ConfigLoaded(config),
+                    _ => Msg::ConfigLoaded(Config::default()),
                 }
             },
-        ))
+        ));
+    }
+
+    /// The API key set by the user, or the one from `config.json`
+    fn api_key(&self) -> Option<String> {
+        self.state
+            .settings
+            .api_key
+            .clone()
+            .or_else(|| self.config.api_key.clone())
+            .filter(|key| !key.is_empty())
+    }
+
+    fn proxy(&self) -> Proxy {
+        self.state
+            .settings
+            .proxy
+            .as_ref()
+            .or(self.config.proxy.as_ref())
+            .map(|proxy| Proxy::parse(proxy))
+            .unwrap_or_default()
+    }
+
+    /// Settings are taken from the url query first, then local storage, then `config.json`
+    ///
+    /// Settings from the url query are remembered in local storage
+    fn config_loaded(&mut self, config: Config) -> bool {
+        self.config = config;
+        if let Some(key) = query_setting(API_KEY_PARAMETER) {
+            self.state.settings.api_key = Some(key);
+        }
+        if let Some(proxy) = query_setting(PROXY_PARAMETER) {
+            self.state.settings.proxy = Some(proxy);
+        }
+        self.persist();
+        if self.api_key().is_none() {
+            self.settings_open = true;
+        }
+        self.reset_settings_draft();
+        self.apply_settings();
+        self.link.send_message(Msg::LoadLocation);
+        true
+    }
+
+    fn apply_settings(&mut self) {
+        self.weather_api.set_api_key(self.api_key());
+        self.weather_api.set_proxy(self.proxy());
+        self.weather_api.set_cache_ttl(
+            self.state
+                .settings
+                .cache_minutes
+                .unwrap_or(DEFAULT_TTL_MINUTES),
+        );
+        self.weather_api
+            .set_preferred_provider(self.state.settings.provider.as_deref());
+        let timeout = match self
+            .state
+            .settings
+            .timeout_seconds
+            .unwrap_or(DEFAULT_TIMEOUT_SECONDS)
+        {
+            0 => None,
+            seconds => Some(Duration::from_secs(u64::from(seconds))),
+        };
+        self.weather_api.set_timeout(timeout);
+        let units = self.state.settings.units;
+        self.pages
+            .iter_mut()
+            .filter_map(|page| page.board.as_mut())
+            .for_each(|board| board.set_units(units));
+        if let Some(body) = utils::document().body() {
+            body.set_class_name(self.state.settings.theme.name());
+        }
+        self.schedule_refresh();
+    }
+
+    fn reset_settings_draft(&mut self) {
+        self.settings_draft = Settings {
+            api_key: self.api_key(),
+            proxy: Some(self.proxy().setting()),
+            ..self.state.settings.clone()
+        };
+    }
+
+    fn edit_settings(&mut self, edit: SettingsEdit) {
+        let draft = &mut self.settings_draft;
+        match edit {
+            SettingsEdit::ApiKey(key) => draft.api_key = Some(key),
+            SettingsEdit::Proxy(proxy) => draft.proxy = Some(proxy),
+            SettingsEdit::Provider(provider) => draft.provider = provider,
+            SettingsEdit::Theme(theme) => draft.theme = theme,
+            SettingsEdit::Units(units) => draft.units = units,
+            SettingsEdit::CacheMinutes(minutes) => draft.cache_minutes = minutes,
+            SettingsEdit::RefreshMinutes(minutes) => draft.refresh_minutes = minutes,
+            SettingsEdit::TimeoutSeconds(seconds) => draft.timeout_seconds = seconds,
+        }
+    }
+
+    fn save_settings(&mut self) -> bool {
+        let draft = self.settings_draft.clone();
+        self.state.settings = Settings {
+            api_key: draft
+                .api_key
+                .map(|key| key.trim().to_owned())
+                .filter(|key| !key.is_empty()),
+            proxy: Some(Proxy::parse(&draft.proxy.unwrap_or_default()).setting()),
+            ..draft
+        };
+        self.persist();
+        self.apply_settings();
+        self.settings_open = false;
+        self.link.send_message(Msg::RefreshAll);
+        true
+    }
+
+    fn toggle_settings(&mut self) -> bool {
+        self.settings_open = !self.settings_open;
+        self.reset_settings_draft();
+        true
+    }
+
+    /// Loads the saved cities, or the location of the user on first launch
+    fn load_location(&mut self) {
+        if !self.pages.is_empty() {
+            self.current_page = self.current_page.min(self.pages.len() - 1);
+            self.link.send_message(Msg::RefreshAll);
+        } else if !self.geolocation.locate(self.link.callback(Msg::Located)) {
+            self.link.send_message(Msg::Located(None));
+        }
+    }
+
+    /// Adds the weather at the position of the user, or the default city if it is unknown
+    /// and no city was added while waiting
+    fn located(&mut self, coordinates: Option<Coordinates>) -> bool {
+        let location = match coordinates {
+            Some(coordinates) => Location::here(coordinates),
+            None if !self.pages.is_empty() => return false,
+            None => {
+                let (title, id) = DEFAULT_LOCATION;
+                Location::new(title.to_owned(), id)
+            }
+        };
+        if self
+            .pages
+            .iter()
+            .any(|page| page.location.key() == location.key())
+        {
+            return false;
+        }
+        // The position comes first, without moving away from a city shown meanwhile
+        self.current_page = if self.pages.is_empty() {
+            0
+        } else {
+            self.current_page + 1
+        };
+        self.pages.insert(0, Page::new(location.clone()));
+        self.persist();
+        self.load_weather(location);
+        true
     }
 
-    fn populate_location_storage(&mut self, bytes: Vec<u8>) {
-        self.location_api.populates(bytes)
+    /// Has the worker load the city index when the search bar gets focus, by click or keyboard
+    fn load_search_bar(&mut self) {
+        match index_url() {
+            Some(url) => self.location_agent.send(LocationRequest::Load(url)),
+            None => self.console.error("Cannot locate the city index"),
+        }
+    }
+
+    fn locations_found(&mut self, response: LocationResponse) -> bool {
+        match response {
+            // Results of a query typed over since are dropped
+            LocationResponse::Found {
+                query,
+                cities,
+                more,
+            } if query == self.search_query && query.len() >= 3 => {
+                self.suggestions = cities;
+                self.more_suggestions = more;
+                true
+            }
+            LocationResponse::Found { .. } => false,
+            LocationResponse::Failed(error) => {
+                self.console.error(&error);
+                false
+            }
+        }
     }
 }
+
+/// Absolute url of the city index, the worker does not resolve urls against the page
+fn index_url() -> Option<String> {
+    let page = utils::window().location().href().ok()?;
+    Url::new_with_base(CITY_INDEX, &page)
+        .ok()
+        .map(|url| url.href())
+}
+
+/// Id of the suggestion element, referenced by the search bar while highlighted
+fn suggestion_id(index: usize) -> String {
+    format!("suggestion{}", index)
+}
+
+/// Horizontal position of the touch that started or ended the event
+fn touch_x(event: &TouchEvent) -> i32 {
+    event
+        .changed_touches()
+        .get(0)
+        .map(|touch| touch.client_x())
+        .unwrap_or_default()
+}
+
+/// A setting given in the url query
+fn query_setting(parameter: &str) -> Option<String> {
+    utils::window()
+        .location()
+        .search()
+        .ok()
+        .and_then(|query| url::query_parameter(&query, parameter))
+        .filter(|setting| !setting.is_empty())
+}
@@ -1 +1,6 @@
+pub mod network;
 pub mod resources;
+pub mod text;
+pub mod units;
+pub mod url;
+pub mod visibility;
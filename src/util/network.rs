@@ -0,0 +1,34 @@
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use yew::utils;
+use yew::Callback;
+
+/// Reports whether the browser is online each time it connects or disconnects
+pub struct NetworkListener {
+    listener: Closure<dyn FnMut()>,
+}
+
+impl NetworkListener {
+    pub fn new(callback: Callback<bool>) -> Self {
+        let listener =
+            Closure::wrap(Box::new(move || callback.emit(is_online())) as Box<dyn FnMut()>);
+        for event in &["online", "offline"] {
+            let _ = utils::window()
+                .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref());
+        }
+        NetworkListener { listener }
+    }
+}
+
+impl Drop for NetworkListener {
+    fn drop(&mut self) {
+        for event in &["online", "offline"] {
+            let _ = utils::window()
+                .remove_event_listener_with_callback(event, self.listener.as_ref().unchecked_ref());
+        }
+    }
+}
+
+pub fn is_online() -> bool {
+    utils::window().navigator().on_line()
+}
@@ -1,5 +1,6 @@
 // The numeric code is provided by OpenWeather to indicate the weather condition
 // The full table and explaination can be found here: https://openweathermap.org/weather-conditions
+// Codes added later fall back to the resource of their group
 
 use crate::api::Condition::{self, *};
 use crate::api::{Atmosphere, Cloud};
@@ -18,6 +19,7 @@ pub fn animation(id: Condition, is_night: bool, feels_like: f32, portrait: bool)
             VolcanicAsh | Squalls | Tornado => ("thunderstorm", true),
             Mist | Smoke | Haze | Fog => ("fog", true),
             SandWhirls | Sand | Dust => ("windy", true),
+            Atmosphere::Unknown(_) => ("fog", true),
         },
         Cloud(cloud) => match cloud {
             Cloud::Clear => {
@@ -37,8 +39,9 @@ pub fn animation(id: Condition, is_night: bool, feels_like: f32, portrait: bool)
                 }
             }
             Cloud::ScatteredClouds => ("partly_cloud", true),
-            Cloud::BrokenClouds | Cloud::OvercastClouds => ("cloudy", true),
+            Cloud::BrokenClouds | Cloud::OvercastClouds | Cloud::Unknown(_) => ("cloudy", true),
         },
+        Condition::Unknown(_) => return None,
     };
     video_name.push_str(weather);
     if append_time_suffix {
@@ -67,6 +70,7 @@ pub fn icon(id: Condition, is_night: bool, feels_like: f32) -> Option<String> {
                 ThunderstormWithLightDrizzle
                 | ThunderstormWithDrizzle
                 | ThunderstormWithHeavyDrizzle => ("thunderstorm_with_drizzle", true),
+                Unknown(_) => ("thunderstorm", false),
             }
         }
         Drizzle(drizzle) => {
@@ -80,7 +84,8 @@ pub fn icon(id: Condition, is_night: bool, feels_like: f32) -> Option<String> {
                 HeavyIntensityDrizzleRain
                 | ShowerRainAndDrizzle
                 | HeavyShowerRainAndDrizzle
-                | ShowerDrizzle => ("drizzle", true),
+                | ShowerDrizzle
+                | Unknown(_) => ("drizzle", true),
             }
         }
         Rain(rain) => {
@@ -97,7 +102,8 @@ pub fn icon(id: Condition, is_night: bool, feels_like: f32) -> Option<String> {
                 | VeryHeavyRain
                 | ExtremeRain
                 | HeavyIntensityShowerRain
-                | RaggedShowerRain => ("rain", true),
+                | RaggedShowerRain
+                | Unknown(_) => ("rain", true),
                 FreezingRain => ("light_shower_snow", false),
             }
         }
@@ -108,7 +114,7 @@ pub fn icon(id: Condition, is_night: bool, feels_like: f32) -> Option<String> {
                     ("rain_snow", false)
                 }
                 LightSnow => ("light_snow", true),
-                Snow => ("snow", true),
+                Snow | Unknown(_) => ("snow", true),
                 HeavySnow => ("heavy_snow", true),
                 LightShowerSleet => ("light_snow_sleet", false),
                 Sleet | ShowerSleet => ("sleet", false),
@@ -117,7 +123,7 @@ pub fn icon(id: Condition, is_night: bool, feels_like: f32) -> Option<String> {
         Atmosphere(atmosphere) => {
             use Atmosphere::*;
             match atmosphere {
-                Mist => ("mist", false),
+                Mist | Unknown(_) => ("mist", false),
                 Smoke | SandWhirls | Sand | Dust | VolcanicAsh | Squalls => ("smoke", false),
                 Fog => ("fog", false),
                 Haze => ("haze", true),
@@ -137,9 +143,10 @@ pub fn icon(id: Condition, is_night: bool, feels_like: f32) -> Option<String> {
                 FewClouds => ("partly_clear", true),
                 ScatteredClouds => ("partly_cloudy", true),
                 BrokenClouds => ("mostly_cloudy", true),
-                OvercastClouds => ("cloudy", true),
+                OvercastClouds | Unknown(_) => ("cloudy", true),
             }
         }
+        Unknown(_) => return None,
     };
     icon_name.push_str(weather);
     if append_time_suffix {
@@ -152,3 +159,42 @@ pub fn icon(id: Condition, is_night: bool, feels_like: f32) -> Option<String> {
     icon_name.push_str(".png");
     Some(icon_name)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_codes_fall_back_to_their_group() {
+        let rain = Condition::from(599);
+        assert_eq!(
+            animation(rain, false, 20.0, true).as_deref(),
+            Some("weather_rain_day.mp4")
+        );
+        assert_eq!(icon(rain, false, 20.0).as_deref(), Some("rain_day.png"));
+        let atmosphere = Condition::from(799);
+        assert_eq!(
+            animation(atmosphere, true, 20.0, false).as_deref(),
+            Some("weather_l_fog_night.mp4")
+        );
+        assert_eq!(
+            icon(Condition::from(899), true, 20.0).as_deref(),
+            Some("cloudy_night.png")
+        );
+        assert_eq!(animation(Condition::from(900), false, 20.0, true), None);
+        assert_eq!(icon(Condition::from(400), false, 20.0), None);
+    }
+
+    #[test]
+    fn every_icon_is_cached_for_offline_use() {
+        let service_worker = include_str!("../../static/service-worker.js");
+        for code in 200..900 {
+            for &(is_night, feels_like) in &[(false, 20.0), (false, 40.0), (true, 20.0)] {
+                if let Some(name) = icon(Condition::from(code), is_night, feels_like) {
+                    let cached = format!("\"icons/{}\"", name);
+                    assert!(service_worker.contains(&cached), "{} is not cached", name);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Matching of user input against names written with accents, or written wrong
+
+/// Lowercases the text and strips the accents of latin letters, so "São Paulo" reads "sao paulo"
+pub fn fold(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    fold_into(text, usize::MAX, &mut folded);
+    folded
+}
+
+/// Folds the first `length` characters of the text into `folded`, replacing what it held,
+/// so a buffer can be reused when folding many texts
+pub fn fold_into(text: &str, length: usize, folded: &mut String) {
+    folded.clear();
+    let mut count = 0;
+    for character in text.chars().flat_map(char::to_lowercase) {
+        let mut encoded = [0; 4];
+        let replacement =
+            fold_char(character).unwrap_or_else(|| character.encode_utf8(&mut encoded));
+        for replaced in replacement.chars() {
+            if count == length {
+                return;
+            }
+            folded.push(replaced);
+            count += 1;
+        }
+    }
+}
+
+fn fold_char(character: char) -> Option<&'static str> {
+    Some(match character {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+/// Levenshtein distances of many texts to the same query
+///
+/// The rows of the distance table are kept between texts, so a text sorted after another
+/// only computes the rows of the characters following their common beginning.
+pub struct Typos {
+    query: Vec<char>,
+    limit: usize,
+    /// Characters of the last text the rows were computed for
+    text: Vec<char>,
+    /// One row of `query.len() + 1` distances for the empty text and each character of `text`
+    rows: Vec<usize>,
+}
+
+impl Typos {
+    pub fn new(query: &str, limit: usize) -> Self {
+        let query = query.chars().collect::<Vec<_>>();
+        Typos {
+            rows: (0..=query.len()).collect(),
+            query,
+            limit,
+            text: vec![],
+        }
+    }
+
+    /// Distance of the text to the query, or `None` once it is certain to exceed the limit
+    pub fn distance(&mut self, text: &str) -> Option<usize> {
+        let width = self.query.len() + 1;
+        let shared = self
+            .text
+            .iter()
+            .zip(text.chars())
+            .take_while(|(kept, character)| *kept == character)
+            .count();
+        self.text.truncate(shared);
+        self.rows.truncate((shared + 1) * width);
+        for character in text.chars().skip(shared) {
+            let previous = self.rows.len() - width;
+            if self.rows[previous..]
+                .iter()
+                .min()
+                .is_some_and(|min| *min > self.limit)
+            {
+                return None;
+            }
+            self.rows.push(self.rows[previous] + 1);
+            for (j, query_character) in self.query.iter().enumerate() {
+                let substitution =
+                    self.rows[previous + j] + usize::from(*query_character != character);
+                let deletion = self.rows[previous + j + 1] + 1;
+                let insertion = self.rows[self.rows.len() - 1] + 1;
+                self.rows.push(substitution.min(deletion).min(insertion));
+            }
+            self.text.push(character);
+        }
+        self.rows
+            .last()
+            .copied()
+            .filter(|distance| *distance <= self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accents_are_folded_and_typos_measured() {
+        assert_eq!(fold("São Paulo,BR"), "sao paulo,br");
+        assert_eq!(fold("Kraków,PL"), "krakow,pl");
+        assert_eq!(fold("Gießen,DE"), "giessen,de");
+        assert_eq!(fold("Москва"), "москва");
+        let mut folded = String::from("left over");
+        fold_into("Gießen,DE", 3, &mut folded);
+        assert_eq!(folded, "gie");
+        fold_into("Gießen,DE", 4, &mut folded);
+        assert_eq!(folded, "gies");
+
+        let mut typos = Typos::new("toronto", 2);
+        assert_eq!(typos.distance("toronto"), Some(0));
+        assert_eq!(typos.distance("torotno"), Some(2));
+        assert_eq!(typos.distance("tornto"), Some(1));
+        assert_eq!(typos.distance("halifax"), None);
+        assert_eq!(typos.distance("to"), None);
+
+        let mut typos = Typos::new("paris", 1);
+        assert_eq!(typos.distance("parid"), Some(1));
+        assert_eq!(typos.distance("paris"), Some(0));
+        assert_eq!(typos.distance("parma"), None);
+        assert_eq!(typos.distance("pa"), None);
+        assert_eq!(typos.distance("xaris"), Some(1));
+    }
+}
@@ -0,0 +1,127 @@
+//! Weather is always fetched in metric, values are converted only when displayed
+
+use serde::{Deserialize, Serialize};
+
+const MPH_PER_METER_PER_SECOND: f32 = 2.236_936;
+const INCH_OF_MERCURY_PER_HECTOPASCAL: f32 = 0.029_53;
+const METERS_PER_MILE: f32 = 1609.344;
+const MILLIMETERS_PER_INCH: f32 = 25.4;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    /// °C, m/s, hPa, m and mm
+    #[default]
+    Metric,
+    /// °F, mph, inHg, mi and in
+    Imperial,
+    /// K, m/s, Pa, m and mm
+    Scientific,
+    /// °C with mph and mi, the rest metric
+    Mixed,
+}
+
+impl Units {
+    pub const ALL: [Units; 4] = [
+        Units::Metric,
+        Units::Imperial,
+        Units::Scientific,
+        Units::Mixed,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+            Units::Scientific => "scientific",
+            Units::Mixed => "mixed",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|units| units.name() == name)
+    }
+
+    pub fn temperature(self, celsius: f32) -> f32 {
+        match self {
+            Units::Metric | Units::Mixed => celsius,
+            Units::Imperial => celsius * 9.0 / 5.0 + 32.0,
+            Units::Scientific => celsius + 273.15,
+        }
+    }
+
+    pub fn temperature_symbol(self) -> &'static str {
+        match self {
+            Units::Metric | Units::Mixed => "°C",
+            Units::Imperial => "°F",
+            Units::Scientific => "K",
+        }
+    }
+
+    pub fn format_temperature(self, celsius: f32) -> String {
+        format!(
+            "{} {}",
+            self.temperature(celsius).round() as isize,
+            self.temperature_symbol()
+        )
+    }
+
+    pub fn format_speed(self, meters_per_second: f32) -> String {
+        match self {
+            Units::Metric | Units::Scientific => format!("{:.1} m/s", meters_per_second),
+            Units::Imperial | Units::Mixed => {
+                format!("{:.1} mph", meters_per_second * MPH_PER_METER_PER_SECOND)
+            }
+        }
+    }
+
+    pub fn format_pressure(self, hectopascal: f32) -> String {
+        match self {
+            Units::Metric | Units::Mixed => format!("{} hPa", hectopascal.round() as isize),
+            Units::Imperial => {
+                format!("{:.2} inHg", hectopascal * INCH_OF_MERCURY_PER_HECTOPASCAL)
+            }
+            Units::Scientific => format!("{} Pa", (hectopascal * 100.0).round() as isize),
+        }
+    }
+
+    pub fn format_distance(self, meters: f32) -> String {
+        match self {
+            Units::Metric | Units::Scientific => format!("{} m", meters.round() as isize),
+            Units::Imperial | Units::Mixed => format!("{:.1} mi", meters / METERS_PER_MILE),
+        }
+    }
+
+    pub fn format_precipitation(self, millimeters: f32) -> String {
+        match self {
+            Units::Imperial => format!("{:.2} in", millimeters / MILLIMETERS_PER_INCH),
+            _ => format!("{:.2} mm", millimeters),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_converted_from_metric() {
+        assert_eq!(Units::Metric.format_temperature(21.4), "21 °C");
+        assert_eq!(Units::Imperial.format_temperature(21.4), "71 °F");
+        assert_eq!(Units::Scientific.format_temperature(21.4), "295 K");
+        assert_eq!(Units::Mixed.format_temperature(21.4), "21 °C");
+
+        assert_eq!(Units::Metric.format_speed(4.1), "4.1 m/s");
+        assert_eq!(Units::Mixed.format_speed(4.1), "9.2 mph");
+
+        assert_eq!(Units::Metric.format_pressure(1013.0), "1013 hPa");
+        assert_eq!(Units::Imperial.format_pressure(1013.0), "29.91 inHg");
+        assert_eq!(Units::Scientific.format_pressure(1013.0), "101300 Pa");
+
+        assert_eq!(Units::Scientific.format_distance(10000.0), "10000 m");
+        assert_eq!(Units::Imperial.format_distance(10000.0), "6.2 mi");
+
+        assert_eq!(Units::Mixed.format_precipitation(2.5), "2.50 mm");
+        assert_eq!(Units::Imperial.format_precipitation(2.54), "0.10 in");
+    }
+}
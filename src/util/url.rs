@@ -0,0 +1,68 @@
+/// Percent-encodes a string so it can be used as a single query component
+pub fn encode(component: &str) -> String {
+    let mut encoded = String::with_capacity(component.len());
+    for byte in component.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Decodes a percent-encoded query component, `+` is treated as a space
+pub fn decode(component: &str) -> String {
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' if index + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[index + 1..index + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        index += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Finds the value of a parameter in a query string like `?apikey=abc&units=metric`
+pub fn query_parameter(query: &str, name: &str) -> Option<String> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter_map(|pair| {
+            let mut split = pair.splitn(2, '=');
+            Some((split.next()?, split.next().unwrap_or_default()))
+        })
+        .find(|(key, _)| decode(key) == name)
+        .map(|(_, value)| decode(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_parameters_round_trip() {
+        let query = format!("?units=metric&apikey={}&empty", encode("a b/ç"));
+        assert_eq!(query_parameter(&query, "apikey").as_deref(), Some("a b/ç"));
+        assert_eq!(query_parameter(&query, "units").as_deref(), Some("metric"));
+        assert_eq!(query_parameter(&query, "empty").as_deref(), Some(""));
+        assert_eq!(query_parameter(&query, "missing"), None);
+        assert_eq!(decode("100%"), "100%");
+    }
+}
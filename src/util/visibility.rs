@@ -0,0 +1,34 @@
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use yew::utils;
+use yew::Callback;
+
+/// Reports whether the page is visible each time the user switches to or away from it
+pub struct VisibilityListener {
+    listener: Closure<dyn FnMut()>,
+}
+
+impl VisibilityListener {
+    pub fn new(callback: Callback<bool>) -> Self {
+        let listener =
+            Closure::wrap(Box::new(move || callback.emit(is_visible())) as Box<dyn FnMut()>);
+        let _ = utils::document().add_event_listener_with_callback(
+            "visibilitychange",
+            listener.as_ref().unchecked_ref(),
+        );
+        VisibilityListener { listener }
+    }
+}
+
+impl Drop for VisibilityListener {
+    fn drop(&mut self) {
+        let _ = utils::document().remove_event_listener_with_callback(
+            "visibilitychange",
+            self.listener.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+pub fn is_visible() -> bool {
+    !utils::document().hidden()
+}
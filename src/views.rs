@@ -1,6 +1,11 @@
-use crate::api::{Condition, LocationWeather};
+use crate::api::{
+    Condition, ForecastWeather, LocationWeather, Origin, Settings, Severity, Theme, WeatherAlerts,
+    WeatherError, DEFAULT_REFRESH_MINUTES, DEFAULT_TIMEOUT_SECONDS, DEFAULT_TTL_MINUTES,
+};
 use crate::util;
-use yew::{html, Callback, Html, MouseEvent};
+use crate::util::units::Units;
+use chrono::{Local, TimeZone, Utc};
+use yew::{html, Callback, ChangeData, Html, InputData, MouseEvent};
 
 pub trait View {
     fn display(&self) -> Html;
@@ -9,20 +14,27 @@ pub trait View {
 pub struct WeatherBoard {
     background: Option<WeatherBackground>,
     today: WeatherToday,
+    chart: Option<ForecastChart>,
+    forecast: Option<ForecastPanel>,
+    alerts: Option<AlertBanner>,
+    portrait: bool,
+    units: Units,
     shade_button_callback: Callback<MouseEvent>,
+    settings_button_callback: Callback<MouseEvent>,
 }
 
 impl WeatherBoard {
     pub fn new(
         title: String,
         weather: LocationWeather,
+        origin: Origin,
         portrait: bool,
         sync_button_callback: Callback<MouseEvent>,
         shade_button_callback: Callback<MouseEvent>,
-    ) -> Self {
-        let title = title;
+        settings_button_callback: Callback<MouseEvent>,
+    ) -> Result<Self, WeatherError> {
         let background = WeatherBackground::new(
-            weather.id(),
+            weather.id()?,
             weather.is_night(),
             weather.temperature.feels_like,
             portrait,
@@ -30,7 +42,7 @@ impl WeatherBoard {
         let sun_rise_time = weather.sun_rise_time();
         let sun_set_time = weather.sun_set_time();
         let icon = WeatherIcon::new(
-            weather.id(),
+            weather.id()?,
             weather.is_night(),
             weather.temperature.feels_like,
         );
@@ -38,14 +50,56 @@ impl WeatherBoard {
             title,
             icon,
             weather,
+            origin,
             sun_rise_time,
             sun_set_time,
+            units: Units::default(),
             sync_button_callback,
         };
-        WeatherBoard {
+        Ok(WeatherBoard {
             background,
             today,
+            chart: None,
+            forecast: None,
+            alerts: None,
+            portrait,
+            units: Units::default(),
             shade_button_callback,
+            settings_button_callback,
+        })
+    }
+
+    /// Unix timestamp of when the weather shown was received
+    pub fn updated(&self) -> i64 {
+        self.today.origin.updated
+    }
+
+    pub fn show_forecast(&mut self, forecast: ForecastWeather) {
+        self.chart
+            .replace(ForecastChart::new(&forecast, self.portrait, self.units));
+        self.forecast
+            .replace(ForecastPanel::new(forecast, self.units));
+    }
+
+    pub fn show_alerts(&mut self, alerts: AlertBanner) {
+        self.alerts.replace(alerts);
+    }
+
+    /// Hides the alert, the others stay
+    pub fn dismiss_alert(&mut self, key: &str) {
+        if let Some(alerts) = self.alerts.as_mut() {
+            alerts.alerts.retain(|alert| alert.key != key);
+        }
+    }
+
+    pub fn set_units(&mut self, units: Units) {
+        self.units = units;
+        self.today.units = units;
+        if let Some(chart) = self.chart.as_mut() {
+            chart.units = units;
+        }
+        if let Some(forecast) = self.forecast.as_mut() {
+            forecast.set_units(units);
         }
     }
 }
@@ -62,16 +116,406 @@ impl View for WeatherBoard {
             <div id="weatherPanel">
             <div id="panelControl">
                 <button class="fn" id="shade" onclick=&self.shade_button_callback>{ "⬇️" }</button>
+                <button class="fn" id="settingsButton" onclick=&self.settings_button_callback>{ "⚙️" }</button>
             </div>
+            {
+                self.alerts.as_ref()
+                    .map(|alerts| alerts.display())
+                    .unwrap_or(html!{})
+            }
             {
                 self.today.display()
             }
+            {
+                self.chart.as_ref()
+                    .map(|chart| chart.display())
+                    .unwrap_or(html!{})
+            }
+            {
+                self.forecast.as_ref()
+                    .map(|forecast| forecast.display())
+                    .unwrap_or(html!{})
+            }
             </div>
         </>
         }
     }
 }
 
+/// Dots to switch between the saved cities, with buttons to reorder and remove them
+pub struct PageControl {
+    titles: Vec<String>,
+    current: usize,
+    select_callback: Callback<usize>,
+    move_callback: Callback<isize>,
+    remove_button_callback: Callback<MouseEvent>,
+}
+
+impl PageControl {
+    pub fn new(
+        titles: Vec<String>,
+        current: usize,
+        select_callback: Callback<usize>,
+        move_callback: Callback<isize>,
+        remove_button_callback: Callback<MouseEvent>,
+    ) -> Self {
+        PageControl {
+            titles,
+            current,
+            select_callback,
+            move_callback,
+            remove_button_callback,
+        }
+    }
+}
+
+impl View for PageControl {
+    fn display(&self) -> Html {
+        let dots = self.titles.iter().enumerate().map(|(index, title)| {
+            let class = if index == self.current {
+                "current"
+            } else {
+                ""
+            };
+            html! {
+                <li class=class title=title onclick=self.select_callback.reform(move |_| index)>{ "●" }</li>
+            }
+        });
+        let last = self.titles.len().saturating_sub(1);
+        html! {
+            <div id="pageControl">
+                <button class="fn" title="Move city left"
+                    disabled={ self.current == 0 }
+                    onclick=self.move_callback.reform(|_| -1)>{ "⏪" }</button>
+                <ul id="pageDots">
+                { for dots }
+                </ul>
+                <button class="fn" title="Move city right"
+                    disabled={ self.current >= last }
+                    onclick=self.move_callback.reform(|_| 1)>{ "⏩" }</button>
+                <button class="fn" title="Remove city"
+                    disabled={ self.titles.len() < 2 }
+                    onclick=&self.remove_button_callback>{ "🗑️" }</button>
+            </div>
+        }
+    }
+}
+
+pub struct ErrorBanner {
+    error: WeatherError,
+    retry_button_callback: Callback<MouseEvent>,
+    settings_button_callback: Callback<MouseEvent>,
+    dismiss_button_callback: Callback<MouseEvent>,
+}
+
+impl ErrorBanner {
+    pub fn new(
+        error: WeatherError,
+        retry_button_callback: Callback<MouseEvent>,
+        settings_button_callback: Callback<MouseEvent>,
+        dismiss_button_callback: Callback<MouseEvent>,
+    ) -> Self {
+        ErrorBanner {
+            error,
+            retry_button_callback,
+            settings_button_callback,
+            dismiss_button_callback,
+        }
+    }
+}
+
+impl View for ErrorBanner {
+    fn display(&self) -> Html {
+        html! {
+            <div id="errorBanner" role="alert">
+                <strong>{ &self.error }</strong>
+                <div>{ self.error.suggestion() }</div>
+                <div id="buttonLine">
+                    <button onclick=&self.retry_button_callback>{ "Retry" }</button>
+                    {
+                        if self.error.needs_settings() {
+                            html! {
+                                <button onclick=&self.settings_button_callback>{ "Settings" }</button>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    <button onclick=&self.dismiss_button_callback>{ "Dismiss" }</button>
+                </div>
+            </div>
+        }
+    }
+}
+
+/// Government weather alerts in effect for the city, each dismissible
+pub struct AlertBanner {
+    alerts: Vec<Alert>,
+    dismiss_callback: Callback<String>,
+}
+
+struct Alert {
+    key: String,
+    severity: Severity,
+    event: String,
+    sender: String,
+    time_window: String,
+    description: String,
+}
+
+impl AlertBanner {
+    /// The alerts not over yet at `now`, leaving out the `dismissed` ones
+    pub fn new(
+        alerts: &WeatherAlerts,
+        now: i64,
+        dismissed: &[String],
+        dismiss_callback: Callback<String>,
+    ) -> Self {
+        let alerts = alerts
+            .active(now)
+            .into_iter()
+            .filter(|alert| !dismissed.contains(&alert.key()))
+            .map(|alert| Alert {
+                key: alert.key(),
+                severity: alert.severity(),
+                event: alert.event.clone(),
+                sender: alert.sender_name.clone(),
+                time_window: alerts.time_window(alert),
+                description: alert.description.clone(),
+            })
+            .collect();
+        AlertBanner {
+            alerts,
+            dismiss_callback,
+        }
+    }
+}
+
+impl View for AlertBanner {
+    fn display(&self) -> Html {
+        if self.alerts.is_empty() {
+            return html! {};
+        }
+        let alerts = self.alerts.iter().map(|alert| {
+            let key = alert.key.clone();
+            let severity = alert.severity.name();
+            html! {
+                <div class=format!("alert {}", severity.to_lowercase())>
+                    <div class="alertHeader">
+                        <strong>{ format!("{} · {}", severity, alert.event) }</strong>
+                        <button aria-label="Dismiss alert"
+                            onclick=self.dismiss_callback.reform(move |_| key.clone())>{ "✕" }</button>
+                    </div>
+                    <div class="alertWindow">{ format!("{} · {}", alert.time_window, alert.sender) }</div>
+                    <p class="alertDescription">{ &alert.description }</p>
+                </div>
+            }
+        });
+        html! {
+            <div id="alertBanner" role="alert">
+                { for alerts }
+            </div>
+        }
+    }
+}
+
+/// Shown while the weather is requested again after a failure
+pub struct RetryNotice {
+    retries: u32,
+}
+
+impl RetryNotice {
+    pub fn new(retries: u32) -> Self {
+        RetryNotice { retries }
+    }
+}
+
+impl View for RetryNotice {
+    fn display(&self) -> Html {
+        html! {
+            <div id="retryNotice" role="status">
+                { format!("The weather service is not responding, retrying… (attempt {})", self.retries + 1) }
+            </div>
+        }
+    }
+}
+
+/// Shown while the browser is offline, with the time the weather shown was received
+pub struct OfflineNotice {
+    updated: Option<i64>,
+}
+
+impl OfflineNotice {
+    pub fn new(updated: Option<i64>) -> Self {
+        OfflineNotice { updated }
+    }
+}
+
+impl View for OfflineNotice {
+    fn display(&self) -> Html {
+        let time = self
+            .updated
+            .and_then(|updated| Local.timestamp_opt(updated, 0).single());
+        html! {
+            <div id="offlineNotice" role="status">
+            {
+                match time {
+                    Some(time) => format!("Offline — showing data from {}", time.format("%H:%M")),
+                    None => "Offline — no weather saved for this city".to_owned(),
+                }
+            }
+            </div>
+        }
+    }
+}
+
+/// A change made in the settings dialog
+pub enum SettingsEdit {
+    ApiKey(String),
+    Proxy(String),
+    /// Name of the provider to try first, `None` for the default order
+    Provider(Option<String>),
+    Theme(Theme),
+    Units(Units),
+    /// Minutes weather is served from the cache, `None` for the default
+    CacheMinutes(Option<u32>),
+    /// Minutes between automatic refreshes, `None` for the default
+    RefreshMinutes(Option<u32>),
+    /// Seconds a request waits for its response, `None` for the default
+    TimeoutSeconds(Option<u32>),
+}
+
+pub struct SettingsDialog {
+    draft: Settings,
+    providers: Vec<&'static str>,
+    api_key_missing: bool,
+    edit_callback: Callback<SettingsEdit>,
+    save_button_callback: Callback<MouseEvent>,
+    close_button_callback: Callback<MouseEvent>,
+}
+
+impl SettingsDialog {
+    pub fn new(
+        draft: Settings,
+        providers: Vec<&'static str>,
+        api_key_missing: bool,
+        edit_callback: Callback<SettingsEdit>,
+        save_button_callback: Callback<MouseEvent>,
+        close_button_callback: Callback<MouseEvent>,
+    ) -> Self {
+        SettingsDialog {
+            draft,
+            providers,
+            api_key_missing,
+            edit_callback,
+            save_button_callback,
+            close_button_callback,
+        }
+    }
+}
+
+/// The value of the option selected
+fn selected_value(change: ChangeData) -> String {
+    match change {
+        ChangeData::Select(select) => select.value(),
+        ChangeData::Value(value) => value,
+        ChangeData::Files(_) => String::new(),
+    }
+}
+
+impl View for SettingsDialog {
+    fn display(&self) -> Html {
+        let provider = self.draft.provider.clone().unwrap_or_default();
+        let providers = self.providers.iter().map(|name| {
+            html! {
+                <option value=name selected={ *name == provider }>{ name }</option>
+            }
+        });
+        let units = Units::ALL.iter().map(|units| {
+            html! {
+                <option value=units.name() selected={ *units == self.draft.units }>{ units.name() }</option>
+            }
+        });
+        let themes = Theme::ALL.iter().map(|theme| {
+            html! {
+                <option value=theme.name() selected={ *theme == self.draft.theme }>{ theme.name() }</option>
+            }
+        });
+        html! {
+            <div id="settings">
+                <h2>{ "Settings" }</h2>
+                {
+                    if self.api_key_missing {
+                        html! {
+                            <p class="prompt">
+                                { "No OpenWeather API key is configured, weather may be unavailable. " }
+                                { "Get a free key from " }
+                                <a href="https://openweathermap.org/api" target="_blank">{ "openweathermap.org" }</a>
+                                { " and paste it below." }
+                            </p>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                <label for="apiKey">{ "OpenWeather API key" }</label>
+                <input id="apiKey"
+                    placeholder="Paste your API key here"
+                    value=self.draft.api_key.clone().unwrap_or_default()
+                    oninput=self.edit_callback.reform(|e: InputData| SettingsEdit::ApiKey(e.value))
+                />
+                <label for="proxy">{ "CORS proxy" }</label>
+                <input id="proxy"
+                    placeholder="none, https://proxy/ or http://proxy/?url={url}"
+                    value=self.draft.proxy.clone().unwrap_or_default()
+                    oninput=self.edit_callback.reform(|e: InputData| SettingsEdit::Proxy(e.value))
+                />
+                <label for="units">{ "Units" }</label>
+                <select id="units"
+                    onchange=self.edit_callback.reform(|change| {
+                        SettingsEdit::Units(Units::from_name(&selected_value(change)).unwrap_or_default())
+                    })>
+                    { for units }
+                </select>
+                <label for="cacheMinutes">{ "Minutes weather is kept before asking for it again" }</label>
+                <input id="cacheMinutes" type="number" min="0"
+                    value=self.draft.cache_minutes.unwrap_or(DEFAULT_TTL_MINUTES).to_string()
+                    oninput=self.edit_callback.reform(|e: InputData| SettingsEdit::CacheMinutes(e.value.trim().parse().ok()))
+                />
+                <label for="refreshMinutes">{ "Minutes between automatic refreshes, 0 to turn off" }</label>
+                <input id="refreshMinutes" type="number" min="0"
+                    value=self.draft.refresh_minutes.unwrap_or(DEFAULT_REFRESH_MINUTES).to_string()
+                    oninput=self.edit_callback.reform(|e: InputData| SettingsEdit::RefreshMinutes(e.value.trim().parse().ok()))
+                />
+                <label for="timeoutSeconds">{ "Seconds to wait for the weather service, 0 to wait indefinitely" }</label>
+                <input id="timeoutSeconds" type="number" min="0"
+                    value=self.draft.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS).to_string()
+                    oninput=self.edit_callback.reform(|e: InputData| SettingsEdit::TimeoutSeconds(e.value.trim().parse().ok()))
+                />
+                <label for="preferredProvider">{ "Preferred provider" }</label>
+                <select id="preferredProvider"
+                    onchange=self.edit_callback.reform(|change| {
+                        SettingsEdit::Provider(Some(selected_value(change)).filter(|name| !name.is_empty()))
+                    })>
+                    <option value="" selected={ provider.is_empty() }>{ "default" }</option>
+                    { for providers }
+                </select>
+                <label for="theme">{ "Theme" }</label>
+                <select id="theme"
+                    onchange=self.edit_callback.reform(|change| {
+                        SettingsEdit::Theme(Theme::from_name(&selected_value(change)).unwrap_or_default())
+                    })>
+                    { for themes }
+                </select>
+                <div id="buttonLine">
+                    <button onclick=&self.save_button_callback>{ "Save" }</button>
+                    <button onclick=&self.close_button_callback>{ "Close" }</button>
+                </div>
+            </div>
+        }
+    }
+}
+
 struct WeatherBackground {
     source_video: String,
 }
@@ -125,8 +569,10 @@ struct WeatherToday {
     title: String,
     icon: Option<WeatherIcon>,
     weather: LocationWeather,
+    origin: Origin,
     sun_rise_time: String,
     sun_set_time: String,
+    units: Units,
     sync_button_callback: Callback<MouseEvent>,
 }
 
@@ -134,6 +580,7 @@ impl View for WeatherToday {
     fn display(&self) -> Html {
         let temperature = &self.weather.temperature;
         let wind = &self.weather.wind;
+        let units = self.units;
         html! {
             <div id="today">
                 <div>
@@ -149,14 +596,14 @@ impl View for WeatherToday {
                     <h2 margin-top="0">{ &self.weather.description() }</h2>
                     <div id="temperatures">
                         <div>
-                            <div style="font-size: 40px" class="no_margin_top">{ &format!("{}", temperature.temp.round() as isize) }</div>
+                            <div style="font-size: 40px" class="no_margin_top">{ &format!("{}", units.temperature(temperature.temp).round() as isize) }</div>
                         </div>
                         <div>
-                            <div style="font-size: 30px" class="no_margin_top">{ "°C" }</div>
+                            <div style="font-size: 30px" class="no_margin_top">{ units.temperature_symbol() }</div>
                         </div>
                         <div id="max-min-temp">
-                            <div>{ &format!("{} °C", temperature.temp_max.round() as isize) }</div>
-                            <div>{ &format!("{} °C", temperature.temp_min.round() as isize) }</div>
+                            <div>{ units.format_temperature(temperature.temp_max) }</div>
+                            <div>{ units.format_temperature(temperature.temp_min) }</div>
                         </div>
                     </div>
                 </div>
@@ -164,11 +611,11 @@ impl View for WeatherToday {
                 <table id="table">
                     <tr>
                         <th>{ "Feels Like" }</th>
-                        <td>{ &format!("{} °C", temperature.feels_like.round() as isize) }</td>
+                        <td>{ units.format_temperature(temperature.feels_like) }</td>
                     </tr>
                     <tr>
                         <th>{ "Pressure"}</th>
-                        <td>{ &format!("{} hPa", temperature.pressure) }</td>
+                        <td>{ units.format_pressure(temperature.pressure as f32) }</td>
                     </tr>
                     <tr>
                         <th>{ "Humidity"}</th>
@@ -179,7 +626,7 @@ impl View for WeatherToday {
                             html!{
                             <tr>
                                 <th>{ "Visibility"}</th>
-                                <td>{ &format!("{} m", visibility) }</td>
+                                <td>{ units.format_distance(visibility as f32) }</td>
                             </tr>
                             }
                         } else {
@@ -188,7 +635,7 @@ impl View for WeatherToday {
                     }
                     <tr>
                         <th>{ "Wind Speed"}</th>
-                        <td>{ &format!("{} m/s", wind.speed) }</td>
+                        <td>{ units.format_speed(wind.speed) }</td>
                     </tr>
                     <tr>
                         <th>{ "Wind Degree" }</th>
@@ -210,7 +657,7 @@ impl View for WeatherToday {
                             .map(|rain| html! {
                             <tr>
                                 <th>{ "Rain in 1 hour" }</th>
-                                <td>{ &format!("{:.2} mm", rain) }</td>
+                                <td>{ units.format_precipitation(rain) }</td>
                             </tr>
                             })
                             .unwrap_or(html!{})
@@ -221,7 +668,7 @@ impl View for WeatherToday {
                             .map(|rain| html! {
                             <tr>
                                 <th>{ "Rain in 3 hour" }</th>
-                                <td>{ &format!("{:.2} mm", rain) }</td>
+                                <td>{ units.format_precipitation(rain) }</td>
                             </tr>
                             })
                             .unwrap_or(html!{})
@@ -232,7 +679,7 @@ impl View for WeatherToday {
                             .map(|snow| html! {
                             <tr>
                                 <th>{ "Snow in 1 hour" }</th>
-                                <td>{ &format!("{:.2} mm", snow) }</td>
+                                <td>{ units.format_precipitation(snow) }</td>
                             </tr>
                             })
                             .unwrap_or(html!{})
@@ -243,7 +690,7 @@ impl View for WeatherToday {
                             .map(|snow| html! {
                             <tr>
                                 <th>{ "Snow in 3 hour" }</th>
-                                <td>{ &format!("{:.2} mm", snow) }</td>
+                                <td>{ units.format_precipitation(snow) }</td>
                             </tr>
                             })
                             .unwrap_or(html!{})
@@ -257,21 +704,294 @@ impl View for WeatherToday {
                         <td>{ &format!("{}", self.sun_set_time) }</td>
                     </tr>
                 </table>
+                <div id="provider">
+                    { &format!("Provided by {}, updated {}", self.origin.provider, updated_ago(self.origin.updated)) }
+                </div>
+            </div>
+        }
+    }
+}
+
+/// How long ago the timestamp was, in words
+fn updated_ago(timestamp: i64) -> String {
+    let minutes = (Utc::now().timestamp() - timestamp).max(0) / 60;
+    match minutes {
+        0 => "just now".to_owned(),
+        1 => "1 min ago".to_owned(),
+        2..=59 => format!("{} min ago", minutes),
+        60..=119 => "1 hour ago".to_owned(),
+        _ => format!("{} hours ago", minutes / 60),
+    }
+}
+
+struct ForecastChart {
+    width: f32,
+    height: f32,
+    points: Vec<ChartPoint>,
+    units: Units,
+}
+
+struct ChartPoint {
+    hour: String,
+    temperature: f32,
+    feels_like: f32,
+    precipitation: f32,
+}
+
+impl ForecastChart {
+    const HEIGHT: f32 = 180.0;
+    const MARGIN_LEFT: f32 = 35.0;
+    const MARGIN_RIGHT: f32 = 10.0;
+    const MARGIN_TOP: f32 = 25.0;
+    const MARGIN_BOTTOM: f32 = 25.0;
+    /// Portion of the plot height the tallest precipitation bar may take
+    const PRECIPITATION_RATIO: f32 = 0.4;
+
+    pub fn new(forecast: &ForecastWeather, portrait: bool, units: Units) -> Self {
+        // Phones get the next 24 hours, wider screens get 48 hours
+        let (width, hours) = if portrait { (300.0, 24) } else { (600.0, 48) };
+        let points = forecast
+            .slots
+            .iter()
+            .take(hours / 3)
+            .map(|slot| ChartPoint {
+                hour: forecast.local_time(slot).format("%Hh").to_string(),
+                temperature: slot.temperature.temp,
+                feels_like: slot.temperature.feels_like,
+                precipitation: slot.precipitation(),
+            })
+            .collect();
+        ForecastChart {
+            width,
+            height: Self::HEIGHT,
+            points,
+            units,
+        }
+    }
+
+    fn plot_width(&self) -> f32 {
+        self.width - Self::MARGIN_LEFT - Self::MARGIN_RIGHT
+    }
+
+    fn plot_height(&self) -> f32 {
+        self.height - Self::MARGIN_TOP - Self::MARGIN_BOTTOM
+    }
+
+    fn x(&self, index: usize) -> f32 {
+        let steps = self.points.len().saturating_sub(1).max(1) as f32;
+        Self::MARGIN_LEFT + index as f32 * self.plot_width() / steps
+    }
+
+    fn temperature_range(&self) -> (f32, f32) {
+        let (min, max) = self
+            .points
+            .iter()
+            .flat_map(|point| vec![point.temperature, point.feels_like])
+            .fold((f32::MAX, f32::MIN), |(min, max), temperature| {
+                (min.min(temperature), max.max(temperature))
+            });
+        if max - min < 1.0 {
+            (min - 1.0, max + 1.0)
+        } else {
+            (min.floor(), max.ceil())
+        }
+    }
+
+    fn temperature_y(&self, temperature: f32, (min, max): (f32, f32)) -> f32 {
+        Self::MARGIN_TOP + (max - temperature) / (max - min) * self.plot_height()
+    }
+
+    fn polyline(&self, value: fn(&ChartPoint) -> f32) -> String {
+        let range = self.temperature_range();
+        self.points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| {
+                format!(
+                    "{:.1},{:.1}",
+                    self.x(index),
+                    self.temperature_y(value(point), range)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn precipitation_bars(&self) -> Html {
+        let max_precipitation = self
+            .points
+            .iter()
+            .map(|point| point.precipitation)
+            .fold(1.0, f32::max);
+        let bar_width = self.plot_width() / self.points.len().max(1) as f32 * 0.6;
+        let bottom = self.height - Self::MARGIN_BOTTOM;
+        html! {
+            {
+                for self.points.iter().enumerate()
+                    .filter(|(_, point)| point.precipitation > 0.0)
+                    .map(|(index, point)| {
+                        let bar_height = point.precipitation / max_precipitation
+                            * self.plot_height() * Self::PRECIPITATION_RATIO;
+                        html! {
+                            <rect class="precipitationBar"
+                                x={ format!("{:.1}", self.x(index) - bar_width / 2.0) }
+                                y={ format!("{:.1}", bottom - bar_height) }
+                                width={ format!("{:.1}", bar_width) }
+                                height={ format!("{:.1}", bar_height) }>
+                                <title>{ self.units.format_precipitation(point.precipitation) }</title>
+                            </rect>
+                        }
+                    })
+            }
+        }
+    }
+
+    fn temperature_label(&self, celsius: f32) -> String {
+        format!(
+            "{}{}",
+            self.units.temperature(celsius).round() as isize,
+            self.units.temperature_symbol()
+        )
+    }
+
+    fn axis_labels(&self) -> Html {
+        let (min, max) = self.temperature_range();
+        // Label every 6 hours so the axis does not crowd on narrow screens
+        let label_every = 2;
+        html! {
+            <>
+                <text class="chartLabel" x={ format!("{:.1}", Self::MARGIN_LEFT - 5.0) }
+                    y={ format!("{:.1}", Self::MARGIN_TOP + 4.0) } text-anchor="end">
+                    { self.temperature_label(max) }
+                </text>
+                <text class="chartLabel" x={ format!("{:.1}", Self::MARGIN_LEFT - 5.0) }
+                    y={ format!("{:.1}", self.height - Self::MARGIN_BOTTOM + 4.0) } text-anchor="end">
+                    { self.temperature_label(min) }
+                </text>
+                {
+                    for self.points.iter().enumerate()
+                        .step_by(label_every)
+                        .map(|(index, point)| html! {
+                            <text class="chartLabel" x={ format!("{:.1}", self.x(index)) }
+                                y={ format!("{:.1}", self.height - 8.0) } text-anchor="middle">
+                                { &point.hour }
+                            </text>
+                        })
+                }
+            </>
+        }
+    }
+}
+
+impl View for ForecastChart {
+    fn display(&self) -> Html {
+        if self.points.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div id="chart">
+                <svg width={ format!("{}", self.width) } height={ format!("{}", self.height) }
+                    viewBox={ format!("0 0 {} {}", self.width, self.height) }>
+                    { self.precipitation_bars() }
+                    <polyline class="feelsLikeLine" points={ self.polyline(|point| point.feels_like) } />
+                    <polyline class="temperatureLine" points={ self.polyline(|point| point.temperature) } />
+                    { self.axis_labels() }
+                    <text class="chartLabel temperatureLegend" x={ format!("{}", Self::MARGIN_LEFT) } y="12">{ "Temperature" }</text>
+                    <text class="chartLabel feelsLikeLegend" x={ format!("{}", Self::MARGIN_LEFT + 85.0) } y="12">{ "Feels like" }</text>
+                    <text class="chartLabel precipitationLegend" x={ format!("{}", Self::MARGIN_LEFT + 160.0) } y="12">{ "Precipitation" }</text>
+                </svg>
             </div>
         }
     }
 }
 
-// pub struct ForecastPanel {
-//     coming_weather: Vec<Weather>,
-// }
-//
-// impl View for ForecastPanel {
-//     fn display(&self) -> Html {
-//         html! {
-//             <div>
-//                 <h2>{ self.coming_weather.len() }</h2>
-//             </div>
-//         }
-//     }
-// }
+struct ForecastPanel {
+    days: Vec<(String, Vec<ForecastSlotView>)>,
+}
+
+struct ForecastSlotView {
+    time: String,
+    icon: Option<String>,
+    description: String,
+    temperature: f32,
+    precipitation_probability: Option<f32>,
+    units: Units,
+}
+
+impl ForecastPanel {
+    pub fn new(forecast: ForecastWeather, units: Units) -> Self {
+        let days = forecast
+            .days()
+            .into_iter()
+            .map(|(day, slots)| {
+                let slots = slots
+                    .into_iter()
+                    .map(|slot| ForecastSlotView {
+                        time: forecast.local_time(slot).format("%H:%M").to_string(),
+                        icon: slot.id().ok().and_then(|id| {
+                            util::resources::icon(id, slot.is_night(), slot.temperature.feels_like)
+                        }),
+                        description: slot.description(),
+                        temperature: slot.temperature.temp,
+                        precipitation_probability: slot.precipitation_probability,
+                        units,
+                    })
+                    .collect();
+                (day, slots)
+            })
+            .collect();
+        ForecastPanel { days }
+    }
+
+    fn set_units(&mut self, units: Units) {
+        self.days
+            .iter_mut()
+            .flat_map(|(_, slots)| slots.iter_mut())
+            .for_each(|slot| slot.units = units);
+    }
+}
+
+impl View for ForecastPanel {
+    fn display(&self) -> Html {
+        html! {
+            <div id="forecast">
+            {
+                for self.days.iter().map(|(day, slots)| html! {
+                    <div class="forecastDay">
+                        <h3>{ day }</h3>
+                        <ul>
+                        { for slots.iter().map(|slot| slot.display()) }
+                        </ul>
+                    </div>
+                })
+            }
+            </div>
+        }
+    }
+}
+
+impl View for ForecastSlotView {
+    fn display(&self) -> Html {
+        html! {
+            <li title={ &self.description }>
+                <div>{ &self.time }</div>
+                {
+                    self.icon.as_ref()
+                        .map(|icon| html! {
+                            <img class="forecastIcon" src={ &format!("icons/{}", icon) } />
+                        })
+                        .unwrap_or(html!{})
+                }
+                <div>{ self.units.format_temperature(self.temperature) }</div>
+                {
+                    self.precipitation_probability
+                        .filter(|probability| *probability > 0.0)
+                        .map(|probability| html! {
+                            <div class="precipitation">{ &format!("💧{}%", (probability * 100.0).round() as isize) }</div>
+                        })
+                        .unwrap_or(html!{})
+                }
+            </li>
+        }
+    }
+}
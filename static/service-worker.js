@@ -0,0 +1,100 @@
+// Keeps the app working offline: the files below are cached when the worker installs,
+// and every other file of the app is cached the first time it is loaded.
+// Weather is not cached here, the app keeps the last weather of each city in local storage.
+
+// v1 cached pages under their query string, which may hold the API key
+const CACHE = "weather-v2";
+
+const PRECACHED = [
+    "./",
+    "index.html",
+    "manifest.webmanifest",
+    "styles.css",
+    "weather_wasm.js",
+    "weather_wasm_bg.wasm",
+    "worker/city_worker.js",
+    "worker/city_worker_bg.wasm",
+    "city.index",
+    // Every icon named by `util::resources::icon`
+    "icons/clear_day.png",
+    "icons/clear_night.png",
+    "icons/cloudy_day.png",
+    "icons/cloudy_night.png",
+    "icons/drizzle_day.png",
+    "icons/drizzle_night.png",
+    "icons/fog.png",
+    "icons/haze_day.png",
+    "icons/haze_night.png",
+    "icons/heavy_snow_day.png",
+    "icons/heavy_snow_night.png",
+    "icons/hot.png",
+    "icons/light_drizzle_day.png",
+    "icons/light_drizzle_night.png",
+    "icons/light_rain_night.png",
+    "icons/light_shower_snow.png",
+    "icons/light_snow_day.png",
+    "icons/light_snow_night.png",
+    "icons/light_snow_sleet.png",
+    "icons/mist.png",
+    "icons/mostly_cloudy_day.png",
+    "icons/mostly_cloudy_night.png",
+    "icons/partly_clear_day.png",
+    "icons/partly_clear_night.png",
+    "icons/partly_cloudy_day.png",
+    "icons/partly_cloudy_night.png",
+    "icons/rain_day.png",
+    "icons/rain_night.png",
+    "icons/rain_snow.png",
+    "icons/sleet.png",
+    "icons/smoke.png",
+    "icons/snow_day.png",
+    "icons/snow_night.png",
+    "icons/thunderstorm.png",
+    "icons/thunderstorm_with_drizzle_day.png",
+    "icons/thunderstorm_with_drizzle_night.png",
+    "icons/thunderstorm_with_rain_day.png",
+    "icons/thunderstorm_with_rain_night.png",
+    "icons/tornado.png",
+];
+
+self.addEventListener("install", event => {
+    // A file missing from the deployment, like an icon, should not keep the others from being cached
+    event.waitUntil(
+        caches.open(CACHE)
+            .then(cache => Promise.all(PRECACHED.map(file => cache.add(file).catch(() => undefined))))
+            .then(() => self.skipWaiting())
+    );
+});
+
+self.addEventListener("activate", event => {
+    event.waitUntil(
+        caches.keys()
+            .then(keys => Promise.all(keys.filter(key => key !== CACHE).map(key => caches.delete(key))))
+            .then(() => self.clients.claim())
+    );
+});
+
+// Files of the app are fetched from the network when possible so updates show up,
+// and from the cache when offline
+self.addEventListener("fetch", event => {
+    const request = event.request;
+    const url = new URL(request.url);
+    if (request.method !== "GET" || url.origin !== self.location.origin) {
+        return;
+    }
+    // Pages are cached without their query string, like `?apikey=…`, so it is not kept on disk
+    const key = request.mode === "navigate" ? url.origin + url.pathname : request;
+    event.respondWith(
+        fetch(request)
+            .then(response => {
+                // Partial responses, like the ones of videos, cannot be cached
+                if (response.status === 200) {
+                    const copy = response.clone();
+                    caches.open(CACHE).then(cache => cache.put(key, copy));
+                }
+                return response;
+            })
+            .catch(() => caches.match(key, { ignoreSearch: true })
+                .then(cached => cached || Response.error()))
+    );
+});